@@ -0,0 +1,290 @@
+// batch.rs
+
+use axum::Json;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount, hash::Hash, instruction::Instruction,
+    pubkey::Pubkey,
+};
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::compute_budget::ComputeBudgetOptions;
+use crate::limits::{MAX_COMPUTE_UNIT_LIMIT, check_limits};
+use crate::transaction::{
+    LookupTableInput, compile_message, parse_instructions, unsigned_transaction,
+};
+use crate::{ApiResponse, ApiResult, InstructionData};
+
+/// Constraints every planned transaction has to satisfy besides the packet and lock limits.
+pub struct BatchConstraints<'a> {
+    /// Instructions repeated at the front of every transaction, e.g. compute budget.
+    pub prefix: &'a [Instruction],
+    pub lookup_tables: &'a [AddressLookupTableAccount],
+    /// Estimated cost of each instruction; when unset compute is not a constraint.
+    pub units_per_instruction: Option<u32>,
+    pub unit_limit: u32,
+}
+
+fn fits(
+    fee_payer: &Pubkey,
+    batch: &[Instruction],
+    constraints: &BatchConstraints,
+) -> Result<bool, String> {
+    if let Some(units) = constraints.units_per_instruction
+        && units as u64 * batch.len() as u64 > constraints.unit_limit as u64
+    {
+        return Ok(false);
+    }
+    let instructions: Vec<Instruction> = constraints.prefix.iter().chain(batch).cloned().collect();
+    let message = compile_message(
+        fee_payer,
+        &instructions,
+        Hash::default(),
+        None,
+        constraints.lookup_tables,
+    )?;
+    Ok(check_limits(&unsigned_transaction(message)).is_valid())
+}
+
+/// Splits `instructions` into the fewest consecutive runs that each fit in one transaction.
+/// Packing greedily is optimal here because order is preserved and every limit is monotone in
+/// the number of instructions.
+pub fn plan_batches(
+    fee_payer: &Pubkey,
+    instructions: &[Instruction],
+    constraints: &BatchConstraints,
+) -> Result<Vec<Range<usize>>, String> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    for end in 1..=instructions.len() {
+        if fits(fee_payer, &instructions[start..end], constraints)? {
+            continue;
+        }
+        // The run before this instruction closes a batch; the instruction opens the next one,
+        // provided it fits on its own.
+        if end - start > 1 {
+            batches.push(start..end - 1);
+            start = end - 1;
+            if fits(fee_payer, &instructions[start..end], constraints)? {
+                continue;
+            }
+        }
+        return Err(format!(
+            "Instruction {start} does not fit in a transaction on its own"
+        ));
+    }
+    if start < instructions.len() {
+        batches.push(start..instructions.len());
+    }
+    Ok(batches)
+}
+
+/// Plans `instructions` into unsigned transactions, each prefixed with `constraints.prefix`.
+pub fn plan_transactions_for(
+    fee_payer: &Pubkey,
+    instructions: &[Instruction],
+    constraints: &BatchConstraints,
+    blockhash: Hash,
+) -> Result<PlanTransactionsData, String> {
+    let batches = plan_batches(fee_payer, instructions, constraints)?;
+    let mut transactions = Vec::with_capacity(batches.len());
+    for range in batches {
+        let batch: Vec<Instruction> = constraints
+            .prefix
+            .iter()
+            .chain(&instructions[range.clone()])
+            .cloned()
+            .collect();
+        let message = compile_message(
+            fee_payer,
+            &batch,
+            blockhash,
+            None,
+            constraints.lookup_tables,
+        )?;
+        let num_signers = message.header().num_required_signatures as usize;
+        let signers = message.static_account_keys()[..num_signers]
+            .iter()
+            .map(|k| k.to_string())
+            .collect();
+        let wire = bincode::serialize(&unsigned_transaction(message))
+            .map_err(|e| format!("Serialization error: {e}"))?;
+        transactions.push(PlannedTransaction {
+            instructions: range.collect(),
+            instruction_data: batch.iter().map(InstructionData::from).collect(),
+            size: wire.len(),
+            transaction: BASE64.encode(wire),
+            signers,
+        });
+    }
+    Ok(PlanTransactionsData { transactions })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanTransactionsRequest {
+    instructions: Vec<InstructionData>,
+    fee_payer: String,
+    /// Defaults to the zero hash, for callers that only want the grouping.
+    recent_blockhash: Option<String>,
+    #[serde(default)]
+    address_lookup_tables: Vec<LookupTableInput>,
+    compute_units_per_instruction: Option<u32>,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+#[derive(Serialize)]
+struct PlannedTransaction {
+    /// Indexes into the request's instruction list.
+    instructions: Vec<usize>,
+    /// The transaction's instructions, prefix included, as accepted by `/transaction/build`.
+    instruction_data: Vec<InstructionData>,
+    transaction: String,
+    size: usize,
+    signers: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct PlanTransactionsData {
+    transactions: Vec<PlannedTransaction>,
+}
+
+pub async fn plan_transactions(
+    Json(payload): Json<PlanTransactionsRequest>,
+) -> ApiResult<PlanTransactionsData> {
+    let fee_payer = Pubkey::from_str(&payload.fee_payer)
+        .map_err(|_| Json(ApiResponse::err("Invalid fee payer pubkey")))?;
+    let blockhash = match &payload.recent_blockhash {
+        Some(hash) => {
+            Hash::from_str(hash).map_err(|_| Json(ApiResponse::err("Invalid recent blockhash")))?
+        }
+        None => Hash::default(),
+    };
+    let instructions =
+        parse_instructions(&payload.instructions).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let lookup_tables = payload
+        .address_lookup_tables
+        .iter()
+        .map(LookupTableInput::to_account)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+    let prefix = payload.compute_budget.instructions();
+    let constraints = BatchConstraints {
+        prefix: &prefix,
+        lookup_tables: &lookup_tables,
+        units_per_instruction: payload.compute_units_per_instruction,
+        unit_limit: payload
+            .compute_budget
+            .unit_limit()
+            .unwrap_or(MAX_COMPUTE_UNIT_LIMIT),
+    };
+
+    let plan = plan_transactions_for(&fee_payer, &instructions, &constraints, blockhash)
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+    Ok(Json(ApiResponse::ok(plan)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::packet::PACKET_DATA_SIZE;
+
+    /// A memo with no signers, so every instruction costs exactly its data plus three bytes
+    /// (program index, account count, data length) while under 128 bytes.
+    fn memo(len: usize) -> Instruction {
+        Instruction {
+            program_id: spl_memo::id(),
+            accounts: vec![],
+            data: vec![b'x'; len],
+        }
+    }
+
+    fn constraints(
+        units_per_instruction: Option<u32>,
+        unit_limit: u32,
+    ) -> BatchConstraints<'static> {
+        BatchConstraints {
+            prefix: &[],
+            lookup_tables: &[],
+            units_per_instruction,
+            unit_limit,
+        }
+    }
+
+    fn size(fee_payer: &Pubkey, instructions: &[Instruction]) -> usize {
+        let message = compile_message(fee_payer, instructions, Hash::default(), None, &[]).unwrap();
+        bincode::serialized_size(&unsigned_transaction(message)).unwrap() as usize
+    }
+
+    #[test]
+    fn batches_cover_instructions_in_order() {
+        let fee_payer = Pubkey::new_unique();
+        let instructions: Vec<Instruction> = (0..40).map(|i| memo(20 + i * 3)).collect();
+
+        let batches = plan_batches(
+            &fee_payer,
+            &instructions,
+            &constraints(None, MAX_COMPUTE_UNIT_LIMIT),
+        )
+        .unwrap();
+
+        assert!(batches.len() > 1);
+        let covered: Vec<usize> = batches.into_iter().flatten().collect();
+        assert_eq!(covered, (0..instructions.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn oversized_instruction_is_an_error() {
+        let fee_payer = Pubkey::new_unique();
+        let instructions = vec![memo(10), memo(PACKET_DATA_SIZE), memo(10)];
+
+        let result = plan_batches(
+            &fee_payer,
+            &instructions,
+            &constraints(None, MAX_COMPUTE_UNIT_LIMIT),
+        );
+
+        assert_eq!(
+            result,
+            Err("Instruction 1 does not fit in a transaction on its own".to_string())
+        );
+    }
+
+    #[test]
+    fn splits_at_the_packet_size() {
+        let fee_payer = Pubkey::new_unique();
+        let mut instructions: Vec<Instruction> = (0..9).map(|_| memo(100)).collect();
+        instructions.push(memo(135));
+        assert_eq!(size(&fee_payer, &instructions), PACKET_DATA_SIZE);
+
+        let unlimited = constraints(None, MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(
+            plan_batches(&fee_payer, &instructions, &unlimited).unwrap(),
+            vec![0..10]
+        );
+
+        instructions.push(memo(1));
+        assert_eq!(
+            plan_batches(&fee_payer, &instructions, &unlimited).unwrap(),
+            vec![0..10, 10..11]
+        );
+    }
+
+    #[test]
+    fn splits_at_the_unit_limit() {
+        let fee_payer = Pubkey::new_unique();
+        let instructions: Vec<Instruction> = (0..5).map(|_| memo(10)).collect();
+
+        let batches = plan_batches(
+            &fee_payer,
+            &instructions,
+            &constraints(Some(100_000), 250_000),
+        )
+        .unwrap();
+
+        assert_eq!(batches, vec![0..2, 2..4, 4..5]);
+    }
+}
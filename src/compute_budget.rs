@@ -0,0 +1,56 @@
+// compute_budget.rs
+
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, instruction::Instruction};
+
+use crate::{ApiResponse, ApiResult, InstructionData};
+
+/// Optional compute budget settings accepted by every instruction builder endpoint.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputeBudgetOptions {
+    compute_unit_limit: Option<u32>,
+    /// Priority fee in micro-lamports per compute unit.
+    compute_unit_price: Option<u64>,
+}
+
+impl ComputeBudgetOptions {
+    pub fn unit_limit(&self) -> Option<u32> {
+        self.compute_unit_limit
+    }
+
+    /// The compute budget instructions to place at the front of a transaction, if any.
+    pub fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = Vec::new();
+        if let Some(units) = self.compute_unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.compute_unit_price {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+                micro_lamports,
+            ));
+        }
+        instructions
+    }
+}
+
+#[derive(Serialize)]
+pub struct ComputeBudgetData {
+    instructions: Vec<InstructionData>,
+}
+
+pub async fn compute_budget(
+    Json(payload): Json<ComputeBudgetOptions>,
+) -> ApiResult<ComputeBudgetData> {
+    let instructions = payload.instructions();
+    if instructions.is_empty() {
+        return Err(Json(ApiResponse::err(
+            "Provide computeUnitLimit and/or computeUnitPrice",
+        )));
+    }
+
+    Ok(Json(ApiResponse::ok(ComputeBudgetData {
+        instructions: instructions.iter().map(InstructionData::from).collect(),
+    })))
+}
@@ -0,0 +1,799 @@
+// decode.rs
+
+use axum::Json;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use solana_sdk::{
+    compute_budget, message::VersionedMessage, program_option::COption,
+    program_utils::limited_deserialize, pubkey::Pubkey, signature::Signature,
+    system_instruction::SystemInstruction, system_program,
+};
+use spl_token::instruction::{AuthorityType, TokenInstruction};
+use spl_token_2022::extension::{
+    default_account_state::instruction::{
+        DefaultAccountStateInstruction, decode_instruction as decode_default_account_state,
+    },
+    interest_bearing_mint::instruction::{
+        InitializeInstructionData as InterestBearingInitialize, InterestBearingMintInstruction,
+    },
+    metadata_pointer::instruction::{
+        InitializeInstructionData as MetadataPointerInitialize, MetadataPointerInstruction,
+    },
+    transfer_fee::instruction::TransferFeeInstruction,
+    transfer_hook::instruction::{
+        InitializeInstructionData as TransferHookInitialize, TransferHookInstruction,
+    },
+};
+use spl_token_2022::instruction::{
+    AuthorityType as Token2022AuthorityType, TokenInstruction as Token2022Instruction,
+    decode_instruction_data, decode_instruction_type,
+};
+use spl_token_2022::state::AccountState;
+
+use crate::transaction::{MessageInfo, deserialize_transaction};
+use crate::{ApiResponse, ApiResult, InstructionData};
+
+#[derive(Serialize)]
+pub struct ParsedInstruction {
+    program: String,
+    #[serde(rename = "type")]
+    kind: String,
+    info: Map<String, Value>,
+}
+
+fn parsed(program: &str, kind: &str, info: Map<String, Value>) -> ParsedInstruction {
+    ParsedInstruction {
+        program: program.to_string(),
+        kind: kind.to_string(),
+        info,
+    }
+}
+
+/// Maps positional accounts onto their names, failing if the instruction is short of accounts.
+fn labelled(accounts: &[String], labels: &[&str]) -> Result<Map<String, Value>, String> {
+    if accounts.len() < labels.len() {
+        return Err(format!(
+            "Expected at least {} accounts, got {}",
+            labels.len(),
+            accounts.len()
+        ));
+    }
+    Ok(labels
+        .iter()
+        .zip(accounts)
+        .map(|(label, key)| (label.to_string(), json!(key)))
+        .collect())
+}
+
+/// Like `labelled`, but any accounts after the named ones are reported as multisig signers.
+fn labelled_with_signers(
+    accounts: &[String],
+    labels: &[&str],
+) -> Result<Map<String, Value>, String> {
+    let mut info = labelled(accounts, labels)?;
+    if accounts.len() > labels.len() {
+        info.insert("signers".to_string(), json!(accounts[labels.len()..]));
+    }
+    Ok(info)
+}
+
+fn coption_to_json(value: &COption<Pubkey>) -> Value {
+    match value {
+        COption::Some(key) => json!(key.to_string()),
+        COption::None => Value::Null,
+    }
+}
+
+fn authority_type_name(authority_type: &Token2022AuthorityType) -> &'static str {
+    match authority_type {
+        Token2022AuthorityType::MintTokens => "mintTokens",
+        Token2022AuthorityType::FreezeAccount => "freezeAccount",
+        Token2022AuthorityType::AccountOwner => "accountOwner",
+        Token2022AuthorityType::CloseAccount => "closeAccount",
+        Token2022AuthorityType::TransferFeeConfig => "transferFeeConfig",
+        Token2022AuthorityType::WithheldWithdraw => "withheldWithdraw",
+        Token2022AuthorityType::CloseMint => "closeMint",
+        Token2022AuthorityType::InterestRate => "interestRate",
+        Token2022AuthorityType::PermanentDelegate => "permanentDelegate",
+        Token2022AuthorityType::ConfidentialTransferMint => "confidentialTransferMint",
+        Token2022AuthorityType::TransferHookProgramId => "transferHookProgramId",
+        Token2022AuthorityType::ConfidentialTransferFeeConfig => "confidentialTransferFeeConfig",
+        Token2022AuthorityType::MetadataPointer => "metadataPointer",
+        Token2022AuthorityType::GroupPointer => "groupPointer",
+        Token2022AuthorityType::GroupMemberPointer => "groupMemberPointer",
+    }
+}
+
+/// The classic program's authority types are the first four of Token-2022's.
+fn classic_authority_type(authority_type: AuthorityType) -> Token2022AuthorityType {
+    match authority_type {
+        AuthorityType::MintTokens => Token2022AuthorityType::MintTokens,
+        AuthorityType::FreezeAccount => Token2022AuthorityType::FreezeAccount,
+        AuthorityType::AccountOwner => Token2022AuthorityType::AccountOwner,
+        AuthorityType::CloseAccount => Token2022AuthorityType::CloseAccount,
+    }
+}
+
+fn set_authority_info(
+    accounts: &[String],
+    authority_type: &Token2022AuthorityType,
+    new_authority: &COption<Pubkey>,
+) -> Result<Map<String, Value>, String> {
+    let mut info = labelled_with_signers(accounts, &["account", "authority"])?;
+    info.insert(
+        "authorityType".into(),
+        json!(authority_type_name(authority_type)),
+    );
+    info.insert("newAuthority".into(), coption_to_json(new_authority));
+    Ok(info)
+}
+
+fn parse_system(accounts: &[String], data: &[u8]) -> Result<ParsedInstruction, String> {
+    let instruction: SystemInstruction =
+        limited_deserialize(data).map_err(|_| "Invalid system instruction data".to_string())?;
+    let (kind, info) = match instruction {
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let mut info = labelled(accounts, &["source", "newAccount"])?;
+            info.insert("lamports".into(), json!(lamports));
+            info.insert("space".into(), json!(space));
+            info.insert("owner".into(), json!(owner.to_string()));
+            ("createAccount", info)
+        }
+        SystemInstruction::Assign { owner } => {
+            let mut info = labelled(accounts, &["account"])?;
+            info.insert("owner".into(), json!(owner.to_string()));
+            ("assign", info)
+        }
+        SystemInstruction::Transfer { lamports } => {
+            let mut info = labelled(accounts, &["source", "destination"])?;
+            info.insert("lamports".into(), json!(lamports));
+            ("transfer", info)
+        }
+        SystemInstruction::CreateAccountWithSeed {
+            base,
+            seed,
+            lamports,
+            space,
+            owner,
+        } => {
+            let mut info = labelled(accounts, &["source", "newAccount"])?;
+            info.insert("base".into(), json!(base.to_string()));
+            info.insert("seed".into(), json!(seed));
+            info.insert("lamports".into(), json!(lamports));
+            info.insert("space".into(), json!(space));
+            info.insert("owner".into(), json!(owner.to_string()));
+            ("createAccountWithSeed", info)
+        }
+        SystemInstruction::AdvanceNonceAccount => (
+            "advanceNonce",
+            labelled(
+                accounts,
+                &["nonceAccount", "recentBlockhashesSysvar", "nonceAuthority"],
+            )?,
+        ),
+        SystemInstruction::WithdrawNonceAccount(lamports) => {
+            let mut info = labelled(
+                accounts,
+                &[
+                    "nonceAccount",
+                    "destination",
+                    "recentBlockhashesSysvar",
+                    "rentSysvar",
+                    "nonceAuthority",
+                ],
+            )?;
+            info.insert("lamports".into(), json!(lamports));
+            ("withdrawFromNonce", info)
+        }
+        SystemInstruction::InitializeNonceAccount(authority) => {
+            let mut info = labelled(
+                accounts,
+                &["nonceAccount", "recentBlockhashesSysvar", "rentSysvar"],
+            )?;
+            info.insert("nonceAuthority".into(), json!(authority.to_string()));
+            ("initializeNonce", info)
+        }
+        SystemInstruction::AuthorizeNonceAccount(new_authority) => {
+            let mut info = labelled(accounts, &["nonceAccount", "nonceAuthority"])?;
+            info.insert("newAuthorized".into(), json!(new_authority.to_string()));
+            ("authorizeNonce", info)
+        }
+        SystemInstruction::UpgradeNonceAccount => {
+            ("upgradeNonce", labelled(accounts, &["nonceAccount"])?)
+        }
+        SystemInstruction::Allocate { space } => {
+            let mut info = labelled(accounts, &["account"])?;
+            info.insert("space".into(), json!(space));
+            ("allocate", info)
+        }
+        SystemInstruction::AllocateWithSeed {
+            base,
+            seed,
+            space,
+            owner,
+        } => {
+            let mut info = labelled(accounts, &["account"])?;
+            info.insert("base".into(), json!(base.to_string()));
+            info.insert("seed".into(), json!(seed));
+            info.insert("space".into(), json!(space));
+            info.insert("owner".into(), json!(owner.to_string()));
+            ("allocateWithSeed", info)
+        }
+        SystemInstruction::AssignWithSeed { base, seed, owner } => {
+            let mut info = labelled(accounts, &["account"])?;
+            info.insert("base".into(), json!(base.to_string()));
+            info.insert("seed".into(), json!(seed));
+            info.insert("owner".into(), json!(owner.to_string()));
+            ("assignWithSeed", info)
+        }
+        SystemInstruction::TransferWithSeed {
+            lamports,
+            from_seed,
+            from_owner,
+        } => {
+            let mut info = labelled(accounts, &["source", "sourceBase", "destination"])?;
+            info.insert("lamports".into(), json!(lamports));
+            info.insert("sourceSeed".into(), json!(from_seed));
+            info.insert("sourceOwner".into(), json!(from_owner.to_string()));
+            ("transferWithSeed", info)
+        }
+    };
+    Ok(parsed("system", kind, info))
+}
+
+/// Token-2022 shares the classic program's instruction layout for everything but extensions,
+/// so both are decoded here and labelled with `program`.
+fn parse_spl_token(
+    program: &str,
+    accounts: &[String],
+    data: &[u8],
+) -> Result<ParsedInstruction, String> {
+    let instruction =
+        TokenInstruction::unpack(data).map_err(|_| "Invalid spl-token instruction data")?;
+    let (kind, info) = match instruction {
+        TokenInstruction::InitializeMint {
+            decimals,
+            mint_authority,
+            freeze_authority,
+        } => {
+            let mut info = labelled(accounts, &["mint", "rentSysvar"])?;
+            info.insert("decimals".into(), json!(decimals));
+            info.insert("mintAuthority".into(), json!(mint_authority.to_string()));
+            info.insert("freezeAuthority".into(), coption_to_json(&freeze_authority));
+            ("initializeMint", info)
+        }
+        TokenInstruction::InitializeMint2 {
+            decimals,
+            mint_authority,
+            freeze_authority,
+        } => {
+            let mut info = labelled(accounts, &["mint"])?;
+            info.insert("decimals".into(), json!(decimals));
+            info.insert("mintAuthority".into(), json!(mint_authority.to_string()));
+            info.insert("freezeAuthority".into(), coption_to_json(&freeze_authority));
+            ("initializeMint2", info)
+        }
+        TokenInstruction::InitializeAccount => (
+            "initializeAccount",
+            labelled(accounts, &["account", "mint", "owner", "rentSysvar"])?,
+        ),
+        TokenInstruction::InitializeAccount2 { owner } => {
+            let mut info = labelled(accounts, &["account", "mint", "rentSysvar"])?;
+            info.insert("owner".into(), json!(owner.to_string()));
+            ("initializeAccount2", info)
+        }
+        TokenInstruction::InitializeAccount3 { owner } => {
+            let mut info = labelled(accounts, &["account", "mint"])?;
+            info.insert("owner".into(), json!(owner.to_string()));
+            ("initializeAccount3", info)
+        }
+        TokenInstruction::InitializeMultisig { m } => {
+            let mut info = labelled_with_signers(accounts, &["multisig", "rentSysvar"])?;
+            info.insert("m".into(), json!(m));
+            ("initializeMultisig", info)
+        }
+        TokenInstruction::InitializeMultisig2 { m } => {
+            let mut info = labelled_with_signers(accounts, &["multisig"])?;
+            info.insert("m".into(), json!(m));
+            ("initializeMultisig2", info)
+        }
+        TokenInstruction::Transfer { amount } => {
+            let mut info =
+                labelled_with_signers(accounts, &["source", "destination", "authority"])?;
+            info.insert("amount".into(), json!(amount));
+            ("transfer", info)
+        }
+        TokenInstruction::Approve { amount } => {
+            let mut info = labelled_with_signers(accounts, &["source", "delegate", "owner"])?;
+            info.insert("amount".into(), json!(amount));
+            ("approve", info)
+        }
+        TokenInstruction::Revoke => (
+            "revoke",
+            labelled_with_signers(accounts, &["source", "owner"])?,
+        ),
+        TokenInstruction::SetAuthority {
+            authority_type,
+            new_authority,
+        } => (
+            "setAuthority",
+            set_authority_info(
+                accounts,
+                &classic_authority_type(authority_type),
+                &new_authority,
+            )?,
+        ),
+        TokenInstruction::MintTo { amount } => {
+            let mut info = labelled_with_signers(accounts, &["mint", "account", "mintAuthority"])?;
+            info.insert("amount".into(), json!(amount));
+            ("mintTo", info)
+        }
+        TokenInstruction::Burn { amount } => {
+            let mut info = labelled_with_signers(accounts, &["account", "mint", "authority"])?;
+            info.insert("amount".into(), json!(amount));
+            ("burn", info)
+        }
+        TokenInstruction::CloseAccount => (
+            "closeAccount",
+            labelled_with_signers(accounts, &["account", "destination", "owner"])?,
+        ),
+        TokenInstruction::FreezeAccount => (
+            "freezeAccount",
+            labelled_with_signers(accounts, &["account", "mint", "freezeAuthority"])?,
+        ),
+        TokenInstruction::ThawAccount => (
+            "thawAccount",
+            labelled_with_signers(accounts, &["account", "mint", "freezeAuthority"])?,
+        ),
+        TokenInstruction::TransferChecked { amount, decimals } => {
+            let mut info =
+                labelled_with_signers(accounts, &["source", "mint", "destination", "authority"])?;
+            info.insert("amount".into(), json!(amount));
+            info.insert("decimals".into(), json!(decimals));
+            ("transferChecked", info)
+        }
+        TokenInstruction::ApproveChecked { amount, decimals } => {
+            let mut info =
+                labelled_with_signers(accounts, &["source", "mint", "delegate", "owner"])?;
+            info.insert("amount".into(), json!(amount));
+            info.insert("decimals".into(), json!(decimals));
+            ("approveChecked", info)
+        }
+        TokenInstruction::MintToChecked { amount, decimals } => {
+            let mut info = labelled_with_signers(accounts, &["mint", "account", "mintAuthority"])?;
+            info.insert("amount".into(), json!(amount));
+            info.insert("decimals".into(), json!(decimals));
+            ("mintToChecked", info)
+        }
+        TokenInstruction::BurnChecked { amount, decimals } => {
+            let mut info = labelled_with_signers(accounts, &["account", "mint", "authority"])?;
+            info.insert("amount".into(), json!(amount));
+            info.insert("decimals".into(), json!(decimals));
+            ("burnChecked", info)
+        }
+        TokenInstruction::SyncNative => ("syncNative", labelled(accounts, &["account"])?),
+        TokenInstruction::GetAccountDataSize => {
+            ("getAccountDataSize", labelled(accounts, &["mint"])?)
+        }
+        TokenInstruction::InitializeImmutableOwner => (
+            "initializeImmutableOwner",
+            labelled(accounts, &["account"])?,
+        ),
+        TokenInstruction::AmountToUiAmount { amount } => {
+            let mut info = labelled(accounts, &["mint"])?;
+            info.insert("amount".into(), json!(amount));
+            ("amountToUiAmount", info)
+        }
+        TokenInstruction::UiAmountToAmount { ui_amount } => {
+            let mut info = labelled(accounts, &["mint"])?;
+            info.insert("uiAmount".into(), json!(ui_amount));
+            ("uiAmountToAmount", info)
+        }
+    };
+    Ok(parsed(program, kind, info))
+}
+
+fn parse_compute_budget(data: &[u8]) -> Result<ParsedInstruction, String> {
+    let invalid = || "Invalid compute budget instruction data".to_string();
+    let (&tag, rest) = data.split_first().ok_or_else(invalid)?;
+    let u32_arg = || {
+        rest.try_into()
+            .map(u32::from_le_bytes)
+            .map_err(|_| invalid())
+    };
+    let mut info = Map::new();
+    let kind = match tag {
+        1 => {
+            info.insert("bytes".into(), json!(u32_arg()?));
+            "requestHeapFrame"
+        }
+        2 => {
+            info.insert("computeUnitLimit".into(), json!(u32_arg()?));
+            "setComputeUnitLimit"
+        }
+        3 => {
+            let price = rest
+                .try_into()
+                .map(u64::from_le_bytes)
+                .map_err(|_| invalid())?;
+            info.insert("microLamports".into(), json!(price));
+            "setComputeUnitPrice"
+        }
+        4 => {
+            info.insert("bytes".into(), json!(u32_arg()?));
+            "setLoadedAccountsDataSizeLimit"
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(parsed("compute-budget", kind, info))
+}
+
+fn parse_memo(accounts: &[String], data: &[u8]) -> Result<ParsedInstruction, String> {
+    let memo = std::str::from_utf8(data).map_err(|_| "Memo is not valid UTF-8".to_string())?;
+    let mut info = Map::new();
+    info.insert("memo".into(), json!(memo));
+    info.insert("signers".into(), json!(accounts));
+    Ok(parsed("spl-memo", "memo", info))
+}
+
+fn parse_associated_token(accounts: &[String], data: &[u8]) -> Result<ParsedInstruction, String> {
+    let create_labels = [
+        "source",
+        "account",
+        "wallet",
+        "mint",
+        "systemProgram",
+        "tokenProgram",
+    ];
+    let (kind, info) = match data.first() {
+        None | Some(0) => ("create", labelled(accounts, &create_labels)?),
+        Some(1) => ("createIdempotent", labelled(accounts, &create_labels)?),
+        Some(2) => (
+            "recoverNested",
+            labelled(
+                accounts,
+                &[
+                    "nestedSource",
+                    "nestedMint",
+                    "destination",
+                    "nestedOwner",
+                    "ownerMint",
+                    "wallet",
+                    "tokenProgram",
+                ],
+            )?,
+        ),
+        Some(_) => return Err("Invalid associated token account instruction data".to_string()),
+    };
+    Ok(parsed("spl-associated-token-account", kind, info))
+}
+
+/// Decodes the Token-2022 instructions the classic layout lacks, including every extension
+/// initializer `create_token` emits. Other extension sub-instructions, such as rate or pointer
+/// updates, are named but their accounts are left unlabelled.
+fn parse_token_2022_extension(
+    accounts: &[String],
+    data: &[u8],
+) -> Result<ParsedInstruction, String> {
+    let instruction = Token2022Instruction::unpack(data)
+        .map_err(|_| "Invalid spl-token-2022 instruction data")?;
+    let (kind, info) = match instruction {
+        Token2022Instruction::InitializeMintCloseAuthority { close_authority } => {
+            let mut info = labelled(accounts, &["mint"])?;
+            info.insert(
+                "closeAuthority".into(),
+                json!(Option::<Pubkey>::from(close_authority).map(|k| k.to_string())),
+            );
+            ("initializeMintCloseAuthority", info)
+        }
+        // Only extension authority types reach here; the classic ones decode above.
+        Token2022Instruction::SetAuthority {
+            authority_type,
+            new_authority,
+        } => (
+            "setAuthority",
+            set_authority_info(accounts, &authority_type, &new_authority)?,
+        ),
+        Token2022Instruction::InitializeNonTransferableMint => (
+            "initializeNonTransferableMint",
+            labelled(accounts, &["mint"])?,
+        ),
+        Token2022Instruction::InitializePermanentDelegate { delegate } => {
+            let mut info = labelled(accounts, &["mint"])?;
+            info.insert("delegate".into(), json!(delegate.to_string()));
+            ("initializePermanentDelegate", info)
+        }
+        Token2022Instruction::TransferFeeExtension => {
+            match TransferFeeInstruction::unpack(&data[1..])
+                .map_err(|_| "Invalid transfer fee instruction data")?
+            {
+                TransferFeeInstruction::InitializeTransferFeeConfig {
+                    transfer_fee_config_authority,
+                    withdraw_withheld_authority,
+                    transfer_fee_basis_points,
+                    maximum_fee,
+                } => {
+                    let mut info = labelled(accounts, &["mint"])?;
+                    info.insert(
+                        "transferFeeConfigAuthority".into(),
+                        json!(
+                            Option::<Pubkey>::from(transfer_fee_config_authority)
+                                .map(|k| k.to_string())
+                        ),
+                    );
+                    info.insert(
+                        "withdrawWithheldAuthority".into(),
+                        json!(
+                            Option::<Pubkey>::from(withdraw_withheld_authority)
+                                .map(|k| k.to_string())
+                        ),
+                    );
+                    info.insert(
+                        "transferFeeBasisPoints".into(),
+                        json!(transfer_fee_basis_points),
+                    );
+                    info.insert("maximumFee".into(), json!(maximum_fee));
+                    ("initializeTransferFeeConfig", info)
+                }
+                TransferFeeInstruction::TransferCheckedWithFee {
+                    amount,
+                    decimals,
+                    fee,
+                } => {
+                    let mut info = labelled_with_signers(
+                        accounts,
+                        &["source", "mint", "destination", "authority"],
+                    )?;
+                    info.insert("amount".into(), json!(amount));
+                    info.insert("decimals".into(), json!(decimals));
+                    info.insert("fee".into(), json!(fee));
+                    ("transferCheckedWithFee", info)
+                }
+                TransferFeeInstruction::WithdrawWithheldTokensFromMint => (
+                    "withdrawWithheldTokensFromMint",
+                    labelled_with_signers(accounts, &["mint", "destination", "authority"])?,
+                ),
+                TransferFeeInstruction::WithdrawWithheldTokensFromAccounts {
+                    num_token_accounts,
+                } => {
+                    let sources = accounts.len().saturating_sub(num_token_accounts as usize);
+                    let mut info = labelled_with_signers(
+                        &accounts[..sources],
+                        &["mint", "destination", "authority"],
+                    )?;
+                    info.insert("sources".into(), json!(accounts[sources..]));
+                    ("withdrawWithheldTokensFromAccounts", info)
+                }
+                TransferFeeInstruction::HarvestWithheldTokensToMint => {
+                    let mut info = labelled(accounts, &["mint"])?;
+                    info.insert("sources".into(), json!(accounts[1..]));
+                    ("harvestWithheldTokensToMint", info)
+                }
+                _ => unlabelled("transferFeeExtension", accounts),
+            }
+        }
+        Token2022Instruction::DefaultAccountStateExtension => {
+            match decode_default_account_state(&data[1..])
+                .map_err(|_| "Invalid default account state instruction data")?
+            {
+                (DefaultAccountStateInstruction::Initialize, state) => {
+                    let mut info = labelled(accounts, &["mint"])?;
+                    let state = match state {
+                        AccountState::Uninitialized => "uninitialized",
+                        AccountState::Initialized => "initialized",
+                        AccountState::Frozen => "frozen",
+                    };
+                    info.insert("state".into(), json!(state));
+                    ("initializeDefaultAccountState", info)
+                }
+                _ => unlabelled("defaultAccountStateExtension", accounts),
+            }
+        }
+        Token2022Instruction::InterestBearingMintExtension => {
+            match decode_instruction_type(&data[1..])
+                .map_err(|_| "Invalid interest bearing mint instruction data")?
+            {
+                InterestBearingMintInstruction::Initialize => {
+                    let InterestBearingInitialize {
+                        rate_authority,
+                        rate,
+                    } = decode_instruction_data(&data[1..])
+                        .map_err(|_| "Invalid interest bearing mint instruction data")?;
+                    let mut info = labelled(accounts, &["mint"])?;
+                    info.insert("rateAuthority".into(), optional_key(*rate_authority));
+                    info.insert("rate".into(), json!(i16::from(*rate)));
+                    ("initializeInterestBearingConfig", info)
+                }
+                _ => unlabelled("interestBearingMintExtension", accounts),
+            }
+        }
+        Token2022Instruction::MetadataPointerExtension => {
+            match decode_instruction_type(&data[1..])
+                .map_err(|_| "Invalid metadata pointer instruction data")?
+            {
+                MetadataPointerInstruction::Initialize => {
+                    let MetadataPointerInitialize {
+                        authority,
+                        metadata_address,
+                    } = decode_instruction_data(&data[1..])
+                        .map_err(|_| "Invalid metadata pointer instruction data")?;
+                    let mut info = labelled(accounts, &["mint"])?;
+                    info.insert("authority".into(), optional_key(*authority));
+                    info.insert("metadataAddress".into(), optional_key(*metadata_address));
+                    ("initializeMetadataPointer", info)
+                }
+                _ => unlabelled("metadataPointerExtension", accounts),
+            }
+        }
+        Token2022Instruction::TransferHookExtension => {
+            match decode_instruction_type(&data[1..])
+                .map_err(|_| "Invalid transfer hook instruction data")?
+            {
+                TransferHookInstruction::Initialize => {
+                    let TransferHookInitialize {
+                        authority,
+                        program_id,
+                    } = decode_instruction_data(&data[1..])
+                        .map_err(|_| "Invalid transfer hook instruction data")?;
+                    let mut info = labelled(accounts, &["mint"])?;
+                    info.insert("authority".into(), optional_key(*authority));
+                    info.insert("programId".into(), optional_key(*program_id));
+                    ("initializeTransferHook", info)
+                }
+                _ => unlabelled("transferHookExtension", accounts),
+            }
+        }
+        _ => return Err("Unsupported spl-token-2022 instruction".to_string()),
+    };
+    Ok(parsed("spl-token-2022", kind, info))
+}
+
+fn optional_key(key: impl Into<Option<Pubkey>>) -> Value {
+    json!(key.into().map(|k| k.to_string()))
+}
+
+fn unlabelled(kind: &'static str, accounts: &[String]) -> (&'static str, Map<String, Value>) {
+    let mut info = Map::new();
+    info.insert("accounts".into(), json!(accounts));
+    (kind, info)
+}
+
+/// Decodes an instruction for one of the programs this server builds instructions for.
+pub fn parse_instruction(
+    program_id: &Pubkey,
+    accounts: &[String],
+    data: &[u8],
+) -> Result<ParsedInstruction, String> {
+    if *program_id == system_program::id() {
+        parse_system(accounts, data)
+    } else if *program_id == spl_token::id() {
+        parse_spl_token("spl-token", accounts, data)
+    } else if *program_id == spl_token_2022::id() {
+        parse_spl_token("spl-token-2022", accounts, data)
+            .or_else(|_| parse_token_2022_extension(accounts, data))
+    } else if *program_id == compute_budget::id() {
+        parse_compute_budget(data)
+    } else if *program_id == spl_memo::id() {
+        parse_memo(accounts, data)
+    } else if *program_id == spl_associated_token_account::id() {
+        parse_associated_token(accounts, data)
+    } else {
+        Err(format!("Unsupported program {program_id}"))
+    }
+}
+
+/// Resolves every account index of `message` to a printable key. Addresses loaded from lookup
+/// tables are not known to the server, so they are named after their table entry instead.
+fn resolve_account_keys(message: &VersionedMessage) -> Vec<String> {
+    let mut keys: Vec<String> = message
+        .static_account_keys()
+        .iter()
+        .map(|k| k.to_string())
+        .collect();
+    if let VersionedMessage::V0(m) = message {
+        for lookup in &m.address_table_lookups {
+            keys.extend(
+                lookup
+                    .writable_indexes
+                    .iter()
+                    .map(|i| format!("{}[{i}]", lookup.account_key)),
+            );
+        }
+        for lookup in &m.address_table_lookups {
+            keys.extend(
+                lookup
+                    .readonly_indexes
+                    .iter()
+                    .map(|i| format!("{}[{i}]", lookup.account_key)),
+            );
+        }
+    }
+    keys
+}
+
+#[derive(Deserialize)]
+pub struct DecodeTransactionRequest {
+    transaction: String,
+}
+
+#[derive(Serialize)]
+struct DecodedInstruction {
+    program_id: String,
+    accounts: Vec<String>,
+    data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parsed: Option<ParsedInstruction>,
+}
+
+#[derive(Serialize)]
+pub struct DecodeTransactionData {
+    signatures: Vec<Option<String>>,
+    message: MessageInfo,
+    instructions: Vec<DecodedInstruction>,
+}
+
+pub async fn decode_transaction(
+    Json(payload): Json<DecodeTransactionRequest>,
+) -> ApiResult<DecodeTransactionData> {
+    let tx =
+        deserialize_transaction(&payload.transaction).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let keys = resolve_account_keys(&tx.message);
+    let key_at = |index: u8| {
+        keys.get(index as usize)
+            .cloned()
+            .ok_or_else(|| Json(ApiResponse::err("Account index out of range")))
+    };
+
+    let mut instructions = Vec::with_capacity(tx.message.instructions().len());
+    for ix in tx.message.instructions() {
+        let program_id = tx
+            .message
+            .static_account_keys()
+            .get(ix.program_id_index as usize)
+            .ok_or_else(|| Json(ApiResponse::err("Program index out of range")))?;
+        let accounts = ix
+            .accounts
+            .iter()
+            .map(|&i| key_at(i))
+            .collect::<Result<Vec<_>, _>>()?;
+        let parsed = parse_instruction(program_id, &accounts, &ix.data).ok();
+        instructions.push(DecodedInstruction {
+            program_id: program_id.to_string(),
+            accounts,
+            data: BASE64.encode(&ix.data),
+            parsed,
+        });
+    }
+
+    Ok(Json(ApiResponse::ok(DecodeTransactionData {
+        signatures: tx
+            .signatures
+            .iter()
+            .map(|s| (*s != Signature::default()).then(|| s.to_string()))
+            .collect(),
+        message: MessageInfo::from(&tx.message),
+        instructions,
+    })))
+}
+
+/// Reads back an instruction in the `InstructionData` shape returned by the builder endpoints.
+pub async fn decode_instruction(
+    Json(payload): Json<InstructionData>,
+) -> ApiResult<ParsedInstruction> {
+    let instr = payload
+        .to_instruction()
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+    let accounts: Vec<String> = instr
+        .accounts
+        .iter()
+        .map(|a| a.pubkey.to_string())
+        .collect();
+    let parsed = parse_instruction(&instr.program_id, &accounts, &instr.data)
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+    Ok(Json(ApiResponse::ok(parsed)))
+}
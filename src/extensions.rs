@@ -0,0 +1,248 @@
+// extensions.rs
+
+use serde::Deserialize;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use spl_token_2022::{
+    extension::{
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions, default_account_state,
+        interest_bearing_mint, metadata_pointer,
+        transfer_fee::{self, MAX_FEE_BASIS_POINTS},
+        transfer_hook,
+    },
+    instruction::{
+        initialize_mint_close_authority, initialize_non_transferable_mint,
+        initialize_permanent_delegate,
+    },
+    state::{Account, AccountState, Mint},
+};
+use std::str::FromStr;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum DefaultState {
+    Initialized,
+    Frozen,
+}
+
+/// A Token-2022 mint extension and its initial configuration.
+#[derive(Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum MintExtension {
+    TransferFeeConfig {
+        transfer_fee_config_authority: Option<String>,
+        withdraw_withheld_authority: Option<String>,
+        transfer_fee_basis_points: u16,
+        maximum_fee: u64,
+    },
+    InterestBearingConfig {
+        rate_authority: Option<String>,
+        /// Annual rate in basis points; may be negative.
+        rate: i16,
+    },
+    NonTransferable,
+    PermanentDelegate {
+        delegate: String,
+    },
+    DefaultAccountState {
+        state: DefaultState,
+    },
+    MintCloseAuthority {
+        close_authority: String,
+    },
+    MetadataPointer {
+        authority: Option<String>,
+        metadata_address: Option<String>,
+    },
+    TransferHook {
+        authority: Option<String>,
+        program_id: Option<String>,
+    },
+}
+
+impl MintExtension {
+    pub fn extension_type(&self) -> ExtensionType {
+        match self {
+            MintExtension::TransferFeeConfig { .. } => ExtensionType::TransferFeeConfig,
+            MintExtension::InterestBearingConfig { .. } => ExtensionType::InterestBearingConfig,
+            MintExtension::NonTransferable => ExtensionType::NonTransferable,
+            MintExtension::PermanentDelegate { .. } => ExtensionType::PermanentDelegate,
+            MintExtension::DefaultAccountState { .. } => ExtensionType::DefaultAccountState,
+            MintExtension::MintCloseAuthority { .. } => ExtensionType::MintCloseAuthority,
+            MintExtension::MetadataPointer { .. } => ExtensionType::MetadataPointer,
+            MintExtension::TransferHook { .. } => ExtensionType::TransferHook,
+        }
+    }
+
+    /// The instruction that writes this extension into an allocated, uninitialized mint.
+    fn instruction(&self, program_id: &Pubkey, mint: &Pubkey) -> Result<Instruction, String> {
+        match self {
+            MintExtension::TransferFeeConfig {
+                transfer_fee_config_authority,
+                withdraw_withheld_authority,
+                transfer_fee_basis_points,
+                maximum_fee,
+            } => transfer_fee::instruction::initialize_transfer_fee_config(
+                program_id,
+                mint,
+                parse_optional(
+                    transfer_fee_config_authority,
+                    "transfer fee config authority",
+                )?
+                .as_ref(),
+                parse_optional(withdraw_withheld_authority, "withdraw withheld authority")?
+                    .as_ref(),
+                *transfer_fee_basis_points,
+                *maximum_fee,
+            ),
+            MintExtension::InterestBearingConfig {
+                rate_authority,
+                rate,
+            } => interest_bearing_mint::instruction::initialize(
+                program_id,
+                mint,
+                parse_optional(rate_authority, "rate authority")?,
+                *rate,
+            ),
+            MintExtension::NonTransferable => initialize_non_transferable_mint(program_id, mint),
+            MintExtension::PermanentDelegate { delegate } => initialize_permanent_delegate(
+                program_id,
+                mint,
+                &parse(delegate, "permanent delegate")?,
+            ),
+            MintExtension::DefaultAccountState { state } => {
+                let state = match state {
+                    DefaultState::Initialized => AccountState::Initialized,
+                    DefaultState::Frozen => AccountState::Frozen,
+                };
+                default_account_state::instruction::initialize_default_account_state(
+                    program_id, mint, &state,
+                )
+            }
+            MintExtension::MintCloseAuthority { close_authority } => {
+                initialize_mint_close_authority(
+                    program_id,
+                    mint,
+                    Some(&parse(close_authority, "close authority")?),
+                )
+            }
+            MintExtension::MetadataPointer {
+                authority,
+                metadata_address,
+            } => metadata_pointer::instruction::initialize(
+                program_id,
+                mint,
+                parse_optional(authority, "metadata pointer authority")?,
+                parse_optional(metadata_address, "metadata address")?,
+            ),
+            MintExtension::TransferHook {
+                authority,
+                program_id: hook_program,
+            } => transfer_hook::instruction::initialize(
+                program_id,
+                mint,
+                parse_optional(authority, "transfer hook authority")?,
+                parse_optional(hook_program, "transfer hook program id")?,
+            ),
+        }
+        .map_err(|e| format!("Instruction error: {e}"))
+    }
+}
+
+fn parse(key: &str, what: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(key).map_err(|_| format!("Invalid {what} pubkey"))
+}
+
+fn parse_optional(key: &Option<String>, what: &str) -> Result<Option<Pubkey>, String> {
+    key.as_deref().map(|k| parse(k, what)).transpose()
+}
+
+/// Rejects configurations the program would refuse, or that can never take effect, before
+/// anything is sent on-chain.
+pub fn validate(extensions: &[MintExtension], has_freeze_authority: bool) -> Result<(), String> {
+    let types: Vec<ExtensionType> = extensions.iter().map(|e| e.extension_type()).collect();
+    for (i, ty) in types.iter().enumerate() {
+        if types[..i].contains(ty) {
+            return Err(format!("Extension {ty:?} is listed more than once"));
+        }
+    }
+    if types.contains(&ExtensionType::NonTransferable) {
+        for ty in [
+            ExtensionType::TransferFeeConfig,
+            ExtensionType::TransferHook,
+        ] {
+            if types.contains(&ty) {
+                return Err(format!(
+                    "{ty:?} is incompatible with NonTransferable, whose tokens never move"
+                ));
+            }
+        }
+    }
+    ExtensionType::check_for_invalid_mint_extension_combinations(&types)
+        .map_err(|e| e.to_string())?;
+
+    for extension in extensions {
+        match extension {
+            MintExtension::TransferFeeConfig {
+                transfer_fee_basis_points,
+                ..
+            } if *transfer_fee_basis_points > MAX_FEE_BASIS_POINTS => {
+                return Err(format!(
+                    "transferFeeBasisPoints must be at most {MAX_FEE_BASIS_POINTS}"
+                ));
+            }
+            MintExtension::DefaultAccountState {
+                state: DefaultState::Frozen,
+            } if !has_freeze_authority => {
+                return Err("A frozen default account state requires a freezeAuthority".to_string());
+            }
+            MintExtension::MetadataPointer {
+                authority: None,
+                metadata_address: None,
+            } => {
+                return Err(
+                    "metadataPointer needs an authority, a metadataAddress, or both".to_string(),
+                );
+            }
+            MintExtension::TransferHook {
+                authority: None,
+                program_id: None,
+            } => {
+                return Err("transferHook needs an authority, a programId, or both".to_string());
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The mint account size needed to hold `extensions`.
+pub fn mint_len(extensions: &[MintExtension]) -> Result<usize, String> {
+    let types: Vec<ExtensionType> = extensions.iter().map(|e| e.extension_type()).collect();
+    ExtensionType::try_calculate_account_len::<Mint>(&types).map_err(|e| e.to_string())
+}
+
+/// The token account size a mint requires, which grows when its extensions need per-account
+/// state (a transfer fee mint needs room for each account's withheld amount, for instance).
+pub fn account_len_for_mint(mint_data: &[u8]) -> Result<usize, String> {
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)
+        .map_err(|_| "Account data is not a mint".to_string())?;
+    let mint_types = mint.get_extension_types().map_err(|e| e.to_string())?;
+    let account_types = ExtensionType::get_required_init_account_extensions(&mint_types);
+    ExtensionType::try_calculate_account_len::<Account>(&account_types).map_err(|e| e.to_string())
+}
+
+/// Extension initializers, which must all run after allocation and before `initialize_mint`.
+pub fn instructions(
+    extensions: &[MintExtension],
+    program_id: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Vec<Instruction>, String> {
+    extensions
+        .iter()
+        .map(|e| e.instruction(program_id, mint))
+        .collect()
+}
@@ -0,0 +1,98 @@
+// limits.rs
+
+use serde::Serialize;
+use solana_sdk::{
+    message::VersionedMessage, packet::PACKET_DATA_SIZE, transaction::VersionedTransaction,
+};
+
+/// Account lock limit enforced by the runtime per transaction (static keys plus lookups).
+pub const MAX_ACCOUNT_LOCKS: usize = 64;
+
+/// Largest compute unit limit a transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+#[derive(Debug, Serialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LimitViolation {
+    #[error("Transaction is {size} bytes, {overshoot} bytes over the {limit}-byte packet limit")]
+    TooLarge {
+        size: usize,
+        limit: usize,
+        overshoot: usize,
+    },
+    #[error("Transaction locks {count} accounts, {overshoot} over the limit of {limit}")]
+    TooManyAccountLocks {
+        count: usize,
+        limit: usize,
+        overshoot: usize,
+    },
+}
+
+#[derive(Serialize)]
+pub struct TransactionLimits {
+    valid: bool,
+    size: usize,
+    size_limit: usize,
+    num_account_keys: usize,
+    account_lock_limit: usize,
+    num_signatures: usize,
+    violations: Vec<LimitViolation>,
+}
+
+impl TransactionLimits {
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    pub fn violations(&self) -> &[LimitViolation] {
+        &self.violations
+    }
+}
+
+/// Total accounts the message locks, counting addresses loaded through lookup tables.
+fn account_lock_count(message: &VersionedMessage) -> usize {
+    let lookups = match message {
+        VersionedMessage::Legacy(_) => 0,
+        VersionedMessage::V0(m) => m
+            .address_table_lookups
+            .iter()
+            .map(|l| l.writable_indexes.len() + l.readonly_indexes.len())
+            .sum(),
+    };
+    message.static_account_keys().len() + lookups
+}
+
+/// Measures `tx` as it would go over the wire, one signature slot per required signer.
+pub fn check_limits(tx: &VersionedTransaction) -> TransactionLimits {
+    let num_signatures = tx.message.header().num_required_signatures as usize;
+    let mut sized = tx.clone();
+    sized.signatures.resize(num_signatures, Default::default());
+    let size = bincode::serialized_size(&sized).map_or(usize::MAX, |s| s as usize);
+    let num_account_keys = account_lock_count(&tx.message);
+
+    let mut violations = Vec::new();
+    if size > PACKET_DATA_SIZE {
+        violations.push(LimitViolation::TooLarge {
+            size,
+            limit: PACKET_DATA_SIZE,
+            overshoot: size - PACKET_DATA_SIZE,
+        });
+    }
+    if num_account_keys > MAX_ACCOUNT_LOCKS {
+        violations.push(LimitViolation::TooManyAccountLocks {
+            count: num_account_keys,
+            limit: MAX_ACCOUNT_LOCKS,
+            overshoot: num_account_keys - MAX_ACCOUNT_LOCKS,
+        });
+    }
+
+    TransactionLimits {
+        valid: violations.is_empty(),
+        size,
+        size_limit: PACKET_DATA_SIZE,
+        num_account_keys,
+        account_lock_limit: MAX_ACCOUNT_LOCKS,
+        num_signatures,
+        violations,
+    }
+}
@@ -1,23 +1,42 @@
 // main.rs
 
-use axum::{Json, Router, http::StatusCode, response::IntoResponse, routing::post};
-use base64;
-use bs58;
+mod batch;
+mod compute_budget;
+mod decode;
+mod extensions;
+mod limits;
+mod memo;
+mod nonce;
+mod simulate;
+mod token;
+mod transaction;
+mod transfer_fee;
+
+use axum::{Json, Router, routing::post};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
 use ed25519_dalek::{
-    Keypair as DalekKeypair, PUBLIC_KEY_LENGTH, PublicKey as DalekPubkey,
-    Signature as DalekSignature, Signer as DalekSigner, Verifier,
+    Keypair as DalekKeypair, PublicKey as DalekPubkey, Signature as DalekSignature,
+    Signer as DalekSigner, Verifier,
 };
 use serde::{Deserialize, Serialize};
 use solana_sdk::{
-    instruction::Instruction,
+    instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
+    rent::Rent,
     signer::{Signer, keypair::Keypair},
     system_instruction,
 };
-use spl_token::id as spl_token_program_id;
-use spl_token::instruction::{initialize_mint, mint_to, transfer as spl_transfer};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::transfer_fee::instruction::transfer_checked_with_fee;
+use spl_token_2022::instruction::{
+    initialize_mint, initialize_mint2, mint_to, transfer_checked as spl_transfer_checked,
+};
 use std::str::FromStr;
 
+use compute_budget::ComputeBudgetOptions;
+use extensions::MintExtension;
+use token::{TokenDestination, TokenProgram, parse_signers};
+
 #[derive(Serialize)]
 struct ApiResponse<T> {
     success: bool,
@@ -25,6 +44,8 @@ struct ApiResponse<T> {
     data: Option<T>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<serde_json::Value>,
 }
 
 impl<T: Serialize> ApiResponse<T> {
@@ -33,6 +54,7 @@ impl<T: Serialize> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            details: None,
         }
     }
     fn err(msg: &str) -> Self {
@@ -40,6 +62,14 @@ impl<T: Serialize> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(msg.to_string()),
+            details: None,
+        }
+    }
+    /// An error carrying a machine-readable payload alongside the message.
+    fn err_with_details(msg: &str, details: impl Serialize) -> Self {
+        Self {
+            details: serde_json::to_value(details).ok(),
+            ..Self::err(msg)
         }
     }
 }
@@ -60,95 +90,239 @@ async fn generate_keypair() -> ApiResult<KeypairData> {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct CreateTokenRequest {
-    mintAuthority: String,
+    mint_authority: String,
+    /// Without one the mint's accounts can never be frozen.
+    freeze_authority: Option<String>,
     mint: String,
     decimals: u8,
+    /// When set, the mint account is allocated too: `create_account` funded by the payer,
+    /// then `initialize_mint2`. Both the payer and the mint must sign. Required with
+    /// `extensions`.
+    payer: Option<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    /// Token-2022 mint extensions, initialized ahead of the mint itself.
+    #[serde(default)]
+    extensions: Vec<MintExtension>,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct AccountMetaInfo {
     pubkey: String,
     is_signer: bool,
     is_writable: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 struct InstructionData {
     program_id: String,
     accounts: Vec<AccountMetaInfo>,
     instruction_data: String,
 }
 
-async fn create_token(Json(payload): Json<CreateTokenRequest>) -> ApiResult<InstructionData> {
-    let mint = Pubkey::from_str(&payload.mint)
-        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
-    let authority = Pubkey::from_str(&payload.mintAuthority)
-        .map_err(|_| Json(ApiResponse::err("Invalid authority pubkey")))?;
-
-    let instr = initialize_mint(
-        &spl_token_program_id(),
-        &mint,
-        &authority,
-        None,
-        payload.decimals,
-    )
-    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+impl From<&Instruction> for InstructionData {
+    fn from(instr: &Instruction) -> Self {
+        Self {
+            program_id: instr.program_id.to_string(),
+            accounts: instr
+                .accounts
+                .iter()
+                .map(|a| AccountMetaInfo {
+                    pubkey: a.pubkey.to_string(),
+                    is_signer: a.is_signer,
+                    is_writable: a.is_writable,
+                })
+                .collect(),
+            instruction_data: BASE64.encode(&instr.data),
+        }
+    }
+}
 
-    Ok(Json(ApiResponse::ok(InstructionData {
-        program_id: instr.program_id.to_string(),
-        accounts: instr
+impl InstructionData {
+    /// Parses the wire shape back into an `Instruction`, the inverse of the `From` impl above.
+    fn to_instruction(&self) -> Result<Instruction, String> {
+        let program_id = Pubkey::from_str(&self.program_id)
+            .map_err(|_| format!("Invalid program_id {}", self.program_id))?;
+        let accounts = self
             .accounts
             .iter()
-            .map(|a| AccountMetaInfo {
-                pubkey: a.pubkey.to_string(),
-                is_signer: a.is_signer,
-                is_writable: a.is_writable,
+            .map(|a| {
+                Pubkey::from_str(&a.pubkey)
+                    .map(|pubkey| AccountMeta {
+                        pubkey,
+                        is_signer: a.is_signer,
+                        is_writable: a.is_writable,
+                    })
+                    .map_err(|_| format!("Invalid account pubkey {}", a.pubkey))
             })
-            .collect(),
-        instruction_data: base64::encode(&instr.data),
-    })))
+            .collect::<Result<Vec<_>, _>>()?;
+        let data = BASE64
+            .decode(&self.instruction_data)
+            .map_err(|_| "Invalid instruction_data".to_string())?;
+        Ok(Instruction {
+            program_id,
+            accounts,
+            data,
+        })
+    }
+}
+
+/// Builder responses keep the single-instruction shape unless the request asked for extra
+/// instructions (e.g. compute budget), in which case the whole ordered list is returned.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BuilderOutput<T> {
+    Single(T),
+    Multiple { instructions: Vec<InstructionData> },
+}
+
+fn builder_output<T>(
+    instructions: Vec<Instruction>,
+    single: impl FnOnce(&Instruction) -> T,
+) -> BuilderOutput<T> {
+    match instructions.as_slice() {
+        [only] => BuilderOutput::Single(single(only)),
+        _ => BuilderOutput::Multiple {
+            instructions: instructions.iter().map(InstructionData::from).collect(),
+        },
+    }
+}
+
+fn instruction_output(instructions: Vec<Instruction>) -> BuilderOutput<InstructionData> {
+    builder_output(instructions, |instr| InstructionData::from(instr))
+}
+
+async fn create_token(
+    Json(payload): Json<CreateTokenRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+    let authority = Pubkey::from_str(&payload.mint_authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid authority pubkey")))?;
+    let freeze_authority = payload
+        .freeze_authority
+        .as_deref()
+        .map(Pubkey::from_str)
+        .transpose()
+        .map_err(|_| Json(ApiResponse::err("Invalid freeze authority pubkey")))?;
+    let program_id = payload.token_program.id();
+    if !payload.extensions.is_empty() && payload.token_program != TokenProgram::Token2022 {
+        return Err(Json(ApiResponse::err(
+            "Mint extensions require tokenProgram spl-token-2022",
+        )));
+    }
+    // The mint's size depends on its extensions, so the server allocates it rather than
+    // leaving the caller to work that size out.
+    if !payload.extensions.is_empty() && payload.payer.is_none() {
+        return Err(Json(ApiResponse::err(
+            "Mint extensions require a payer to allocate the mint",
+        )));
+    }
+    extensions::validate(&payload.extensions, freeze_authority.is_some())
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+    let setup = extensions::instructions(&payload.extensions, &program_id, &mint)
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    match &payload.payer {
+        Some(payer) => {
+            let payer = Pubkey::from_str(payer)
+                .map_err(|_| Json(ApiResponse::err("Invalid payer pubkey")))?;
+            let space = extensions::mint_len(&payload.extensions)
+                .map_err(|e| Json(ApiResponse::err(&e)))?;
+            instructions.push(system_instruction::create_account(
+                &payer,
+                &mint,
+                Rent::default().minimum_balance(space),
+                space as u64,
+                &program_id,
+            ));
+            instructions.extend(setup);
+            instructions.push(
+                initialize_mint2(
+                    &program_id,
+                    &mint,
+                    &authority,
+                    freeze_authority.as_ref(),
+                    payload.decimals,
+                )
+                .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?,
+            );
+        }
+        // The caller allocated the mint; without extensions it needs no more than `Mint::LEN`.
+        None => {
+            instructions.push(
+                initialize_mint(
+                    &program_id,
+                    &mint,
+                    &authority,
+                    freeze_authority.as_ref(),
+                    payload.decimals,
+                )
+                .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?,
+            );
+        }
+    }
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct MintTokenRequest {
     mint: String,
-    destination: String,
+    #[serde(flatten)]
+    destination: TokenDestination,
     authority: String,
+    /// Multisig members signing for `authority`.
+    #[serde(default)]
+    signers: Vec<String>,
     amount: u64,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
 }
 
-async fn mint_token(Json(payload): Json<MintTokenRequest>) -> ApiResult<InstructionData> {
+async fn mint_token(
+    Json(payload): Json<MintTokenRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
     let mint = Pubkey::from_str(&payload.mint)
         .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
-    let dest = Pubkey::from_str(&payload.destination)
-        .map_err(|_| Json(ApiResponse::err("Invalid destination pubkey")))?;
     let auth = Pubkey::from_str(&payload.authority)
         .map_err(|_| Json(ApiResponse::err("Invalid authority pubkey")))?;
+    let program_id = payload.token_program.id();
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+    // A multisig cannot pay for anything, so its first member funds the destination.
+    let (dest, setup) = payload
+        .destination
+        .resolve(
+            &mint,
+            payload.token_program,
+            signers.first().unwrap_or(&auth),
+        )
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
 
     let instr = mint_to(
-        &spl_token_program_id(),
+        &program_id,
         &mint,
         &dest,
         &auth,
-        &[],
+        &signer_refs,
         payload.amount,
     )
     .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
 
-    Ok(Json(ApiResponse::ok(InstructionData {
-        program_id: instr.program_id.to_string(),
-        accounts: instr
-            .accounts
-            .iter()
-            .map(|a| AccountMetaInfo {
-                pubkey: a.pubkey.to_string(),
-                is_signer: a.is_signer,
-                is_writable: a.is_writable,
-            })
-            .collect(),
-        instruction_data: base64::encode(&instr.data),
-    })))
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.extend(setup);
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
 }
 
 #[derive(Deserialize)]
@@ -173,7 +347,7 @@ async fn sign_message(Json(payload): Json<SignMessageRequest>) -> ApiResult<Sign
     let sig = keypair.sign(payload.message.as_bytes());
 
     Ok(Json(ApiResponse::ok(SignMessageData {
-        signature: base64::encode(sig.to_bytes()),
+        signature: BASE64.encode(sig.to_bytes()),
         public_key: bs58::encode(keypair.public.to_bytes()).into_string(),
         message: payload.message,
     })))
@@ -197,7 +371,8 @@ async fn verify_message(Json(payload): Json<VerifyMessageRequest>) -> ApiResult<
     let pubkey_bytes = bs58::decode(&payload.pubkey)
         .into_vec()
         .map_err(|_| Json(ApiResponse::err("Invalid pubkey")))?;
-    let sig_bytes = base64::decode(&payload.signature)
+    let sig_bytes = BASE64
+        .decode(&payload.signature)
         .map_err(|_| Json(ApiResponse::err("Invalid signature")))?;
     let pubkey = DalekPubkey::from_bytes(&pubkey_bytes)
         .map_err(|_| Json(ApiResponse::err("Invalid pubkey bytes")))?;
@@ -218,6 +393,10 @@ struct SendSolRequest {
     from: String,
     to: String,
     lamports: u64,
+    /// Appended as an SPL Memo instruction signed by `from`.
+    memo: Option<String>,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
 }
 
 #[derive(Serialize)]
@@ -227,63 +406,184 @@ struct SendSolData {
     instruction_data: String,
 }
 
-async fn send_sol(Json(payload): Json<SendSolRequest>) -> ApiResult<SendSolData> {
+async fn send_sol(Json(payload): Json<SendSolRequest>) -> ApiResult<BuilderOutput<SendSolData>> {
     let from =
         Pubkey::from_str(&payload.from).map_err(|_| Json(ApiResponse::err("Invalid from")))?;
     let to = Pubkey::from_str(&payload.to).map_err(|_| Json(ApiResponse::err("Invalid to")))?;
 
     let instr = system_instruction::transfer(&from, &to, payload.lamports);
 
-    Ok(Json(ApiResponse::ok(SendSolData {
-        program_id: instr.program_id.to_string(),
-        accounts: instr
-            .accounts
-            .iter()
-            .map(|a| a.pubkey.to_string())
-            .collect(),
-        instruction_data: base64::encode(&instr.data),
-    })))
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+    if let Some(memo) = &payload.memo {
+        instructions.push(spl_memo::build_memo(memo.as_bytes(), &[&from]));
+    }
+
+    Ok(Json(ApiResponse::ok(builder_output(
+        instructions,
+        |instr| SendSolData {
+            program_id: instr.program_id.to_string(),
+            accounts: instr
+                .accounts
+                .iter()
+                .map(|a| a.pubkey.to_string())
+                .collect(),
+            instruction_data: BASE64.encode(&instr.data),
+        },
+    ))))
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct SendTokenRequest {
-    destination: String,
+    #[serde(flatten)]
+    destination: TokenDestination,
+    /// The sender's token account; defaults to the owner's associated token account for `mint`.
+    source: Option<String>,
+    /// Used to derive associated token accounts and, for checked transfers, to verify decimals.
     mint: String,
+    /// The wallet owning the source account.
     owner: String,
+    /// An approved delegate signing in place of `owner`.
+    delegate: Option<String>,
+    /// Multisig members signing for the owner or delegate.
+    #[serde(default)]
+    signers: Vec<String>,
+    amount: u64,
+    /// Required unless `checked` is false; the program rejects the transfer if it differs
+    /// from the mint's decimals.
+    decimals: Option<u8>,
+    /// Defaults to `transfer_checked`; set to false for the legacy unchecked `transfer`.
+    checked: Option<bool>,
+    /// The fee expected by a Token-2022 mint with a transfer fee, as given by
+    /// /token/transfer-fee/calculate; builds `transfer_checked_with_fee`.
+    fee: Option<u64>,
+    /// Appended as an SPL Memo instruction signed by the transferring authority.
+    memo: Option<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Token-2022 deprecates the unchecked `transfer`, since mints with a transfer fee reject it.
+/// It is only built when a caller opts out of `transfer_checked`.
+#[allow(deprecated)]
+fn unchecked_transfer(
+    program_id: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    signers: &[&Pubkey],
     amount: u64,
+) -> Result<Instruction, solana_sdk::program_error::ProgramError> {
+    spl_token_2022::instruction::transfer(
+        program_id,
+        source,
+        destination,
+        authority,
+        signers,
+        amount,
+    )
 }
 
-async fn send_token(Json(payload): Json<SendTokenRequest>) -> ApiResult<InstructionData> {
+async fn send_token(
+    Json(payload): Json<SendTokenRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
     let mint =
         Pubkey::from_str(&payload.mint).map_err(|_| Json(ApiResponse::err("Invalid mint")))?;
-    let dest = Pubkey::from_str(&payload.destination)
-        .map_err(|_| Json(ApiResponse::err("Invalid destination")))?;
     let owner =
         Pubkey::from_str(&payload.owner).map_err(|_| Json(ApiResponse::err("Invalid owner")))?;
-
-    let instr = spl_transfer(
-        &spl_token_program_id(),
-        &mint,
-        &dest,
-        &owner,
-        &[],
-        payload.amount,
-    )
+    let authority = match &payload.delegate {
+        Some(delegate) => {
+            Pubkey::from_str(delegate).map_err(|_| Json(ApiResponse::err("Invalid delegate")))?
+        }
+        None => owner,
+    };
+    let program_id = payload.token_program.id();
+    let source = match &payload.source {
+        Some(source) => {
+            Pubkey::from_str(source).map_err(|_| Json(ApiResponse::err("Invalid source")))?
+        }
+        None => get_associated_token_address_with_program_id(&owner, &mint, &program_id),
+    };
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+    let (dest, setup) = payload
+        .destination
+        .resolve(
+            &mint,
+            payload.token_program,
+            signers.first().unwrap_or(&authority),
+        )
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+
+    let instr = if payload.checked.unwrap_or(true) {
+        let decimals = payload.decimals.ok_or_else(|| {
+            Json(ApiResponse::err(
+                "decimals is required for transfer_checked; set checked to false for an unchecked transfer",
+            ))
+        })?;
+        match payload.fee {
+            Some(fee) => {
+                if payload.token_program != TokenProgram::Token2022 {
+                    return Err(Json(ApiResponse::err(
+                        "Transfer fees require tokenProgram spl-token-2022",
+                    )));
+                }
+                transfer_checked_with_fee(
+                    &program_id,
+                    &source,
+                    &mint,
+                    &dest,
+                    &authority,
+                    &signer_refs,
+                    payload.amount,
+                    decimals,
+                    fee,
+                )
+            }
+            None => spl_transfer_checked(
+                &program_id,
+                &source,
+                &mint,
+                &dest,
+                &authority,
+                &signer_refs,
+                payload.amount,
+                decimals,
+            ),
+        }
+    } else if payload.fee.is_some() {
+        return Err(Json(ApiResponse::err(
+            "fee is only supported for checked transfers",
+        )));
+    } else {
+        unchecked_transfer(
+            &program_id,
+            &source,
+            &dest,
+            &authority,
+            &signer_refs,
+            payload.amount,
+        )
+    }
     .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
 
-    Ok(Json(ApiResponse::ok(InstructionData {
-        program_id: instr.program_id.to_string(),
-        accounts: instr
-            .accounts
-            .iter()
-            .map(|a| AccountMetaInfo {
-                pubkey: a.pubkey.to_string(),
-                is_signer: a.is_signer,
-                is_writable: a.is_writable,
-            })
-            .collect(),
-        instruction_data: base64::encode(&instr.data),
-    })))
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.extend(setup);
+    instructions.push(instr);
+    if let Some(memo) = &payload.memo {
+        // A multisig account never signs itself; its members do.
+        let memo_signers = if signer_refs.is_empty() {
+            vec![&authority]
+        } else {
+            signer_refs
+        };
+        instructions.push(spl_memo::build_memo(memo.as_bytes(), &memo_signers));
+    }
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
 }
 
 #[tokio::main]
@@ -295,7 +595,56 @@ async fn main() {
         .route("/message/sign", post(sign_message))
         .route("/message/verify", post(verify_message))
         .route("/send/sol", post(send_sol))
-        .route("/send/token", post(send_token));
+        .route("/send/token", post(send_token))
+        .route("/transaction/build", post(transaction::build_transaction))
+        .route("/transaction/sign", post(transaction::sign_transaction))
+        .route("/transaction/plan", post(batch::plan_transactions))
+        .route(
+            "/transaction/validate",
+            post(transaction::validate_transaction),
+        )
+        .route("/transaction/decode", post(decode::decode_transaction))
+        .route("/instruction/decode", post(decode::decode_instruction))
+        .route("/compute-budget", post(compute_budget::compute_budget))
+        .route("/nonce/create", post(nonce::create_nonce))
+        .route("/nonce/advance", post(nonce::advance_nonce))
+        .route("/nonce/withdraw", post(nonce::withdraw_nonce))
+        .route("/nonce/authorize", post(nonce::authorize_nonce))
+        .route("/memo", post(memo::create_memo))
+        .route("/simulate", post(simulate::simulate))
+        .route("/token/ata", post(token::associated_token_address))
+        .route(
+            "/token/ata/create",
+            post(token::create_associated_token_account),
+        )
+        .route("/token/burn", post(token::burn_token))
+        .route("/token/approve", post(token::approve))
+        .route("/token/revoke", post(token::revoke))
+        .route("/token/freeze", post(token::freeze_account))
+        .route("/token/thaw", post(token::thaw_account))
+        .route("/token/set-authority", post(token::set_authority))
+        .route("/token/close", post(token::close_account))
+        .route("/token/close/bulk", post(token::close_accounts))
+        .route("/token/multisig/create", post(token::create_multisig))
+        .route("/token/account/create", post(token::create_token_account))
+        .route(
+            "/token/transfer-fee/calculate",
+            post(transfer_fee::calculate_fee),
+        )
+        .route(
+            "/token/transfer-fee/harvest",
+            post(transfer_fee::harvest_to_mint),
+        )
+        .route(
+            "/token/transfer-fee/withdraw-from-mint",
+            post(transfer_fee::withdraw_from_mint),
+        )
+        .route(
+            "/token/transfer-fee/withdraw-from-accounts",
+            post(transfer_fee::withdraw_from_accounts),
+        )
+        .route("/token/wrap", post(token::wrap_sol))
+        .route("/token/unwrap", post(token::unwrap_sol));
 
     let port = std::env::var("PORT").unwrap_or_else(|_| "3000".to_string());
     let addr = format!("0.0.0.0:{}", port);
@@ -303,3 +652,240 @@ async fn main() {
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use spl_token::instruction::TokenInstruction;
+
+    fn ata(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(wallet, mint, &spl_token::id())
+    }
+
+    fn key() -> Pubkey {
+        Pubkey::new_unique()
+    }
+
+    /// Runs send_token and returns the built instructions, or the error message.
+    async fn send(body: Value) -> Result<Vec<Instruction>, String> {
+        let payload = serde_json::from_value(body).expect("valid request");
+        let data = match send_token(Json(payload)).await {
+            Ok(Json(response)) => serde_json::to_value(response.data).unwrap(),
+            Err(Json(response)) => return Err(response.error.unwrap()),
+        };
+        let list = match data.get("instructions") {
+            Some(list) => list.clone(),
+            None => Value::Array(vec![data]),
+        };
+        let list: Vec<InstructionData> = serde_json::from_value(list).unwrap();
+        Ok(list.iter().map(|i| i.to_instruction().unwrap()).collect())
+    }
+
+    fn keys(instr: &Instruction) -> Vec<Pubkey> {
+        instr.accounts.iter().map(|a| a.pubkey).collect()
+    }
+
+    #[tokio::test]
+    async fn checked_transfer_uses_source_mint_destination_owner() {
+        let (source, mint, dest, owner) = (key(), key(), key(), key());
+        let ixs = send(json!({
+            "source": source.to_string(),
+            "destination": dest.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 7,
+            "decimals": 6,
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(ixs.len(), 1);
+        assert_eq!(keys(&ixs[0]), vec![source, mint, dest, owner]);
+        assert!(ixs[0].accounts[0].is_writable);
+        assert!(!ixs[0].accounts[1].is_writable);
+        assert!(ixs[0].accounts[2].is_writable);
+        assert!(ixs[0].accounts[3].is_signer);
+        assert_eq!(
+            TokenInstruction::unpack(&ixs[0].data).unwrap(),
+            TokenInstruction::TransferChecked {
+                amount: 7,
+                decimals: 6
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn unchecked_transfer_never_puts_mint_in_source_slot() {
+        let (source, mint, dest, owner) = (key(), key(), key(), key());
+        let ixs = send(json!({
+            "source": source.to_string(),
+            "destination": dest.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 7,
+            "checked": false,
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(keys(&ixs[0]), vec![source, dest, owner]);
+        assert!(!keys(&ixs[0]).contains(&mint));
+        assert_eq!(
+            TokenInstruction::unpack(&ixs[0].data).unwrap(),
+            TokenInstruction::Transfer { amount: 7 }
+        );
+    }
+
+    #[tokio::test]
+    async fn source_defaults_to_owner_associated_token_account() {
+        let (mint, dest, owner) = (key(), key(), key());
+        let ixs = send(json!({
+            "destination": dest.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 1,
+            "decimals": 0,
+        }))
+        .await
+        .unwrap();
+
+        let source = ata(&owner, &mint);
+        assert_eq!(keys(&ixs[0]), vec![source, mint, dest, owner]);
+    }
+
+    #[tokio::test]
+    async fn destination_owner_resolves_and_creates_recipient_account() {
+        let (mint, owner, recipient) = (key(), key(), key());
+        let ixs = send(json!({
+            "destinationOwner": recipient.to_string(),
+            "createDestination": true,
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 1,
+            "decimals": 0,
+        }))
+        .await
+        .unwrap();
+
+        let dest = ata(&recipient, &mint);
+        assert_eq!(ixs.len(), 2);
+        assert_eq!(ixs[0].program_id, spl_associated_token_account::id());
+        assert_eq!(ixs[0].accounts[0].pubkey, owner);
+        assert_eq!(ixs[0].accounts[1].pubkey, dest);
+        assert_eq!(keys(&ixs[1]), vec![ata(&owner, &mint), mint, dest, owner]);
+    }
+
+    #[tokio::test]
+    async fn delegate_signs_for_owner_source() {
+        let (mint, dest, owner, delegate) = (key(), key(), key(), key());
+        let ixs = send(json!({
+            "destination": dest.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "delegate": delegate.to_string(),
+            "amount": 1,
+            "decimals": 0,
+        }))
+        .await
+        .unwrap();
+
+        let source = ata(&owner, &mint);
+        assert_eq!(keys(&ixs[0]), vec![source, mint, dest, delegate]);
+        assert!(ixs[0].accounts[3].is_signer);
+    }
+
+    #[tokio::test]
+    async fn multisig_signers_follow_the_authority() {
+        let (source, mint, dest, multisig, a, b) = (key(), key(), key(), key(), key(), key());
+        let ixs = send(json!({
+            "source": source.to_string(),
+            "destination": dest.to_string(),
+            "mint": mint.to_string(),
+            "owner": multisig.to_string(),
+            "signers": [a.to_string(), b.to_string()],
+            "amount": 1,
+            "decimals": 0,
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(keys(&ixs[0]), vec![source, mint, dest, multisig, a, b]);
+        assert!(!ixs[0].accounts[3].is_signer);
+        assert!(ixs[0].accounts[4].is_signer && ixs[0].accounts[5].is_signer);
+    }
+
+    #[tokio::test]
+    async fn checked_transfer_requires_decimals() {
+        let err = send(json!({
+            "destination": key().to_string(),
+            "mint": key().to_string(),
+            "owner": key().to_string(),
+            "amount": 1,
+        }))
+        .await
+        .unwrap_err();
+
+        assert!(err.contains("decimals"));
+    }
+
+    #[tokio::test]
+    async fn token_2022_routes_program_and_account_derivation() {
+        let (mint, owner, recipient) = (key(), key(), key());
+        let ixs = send(json!({
+            "destinationOwner": recipient.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 1,
+            "decimals": 0,
+            "tokenProgram": "spl-token-2022",
+        }))
+        .await
+        .unwrap();
+
+        let derive = |wallet| {
+            get_associated_token_address_with_program_id(wallet, &mint, &spl_token_2022::id())
+        };
+        assert_eq!(ixs[0].program_id, spl_token_2022::id());
+        assert_eq!(
+            keys(&ixs[0]),
+            vec![derive(&owner), mint, derive(&recipient), owner]
+        );
+    }
+
+    #[tokio::test]
+    async fn fee_builds_transfer_checked_with_fee() {
+        let (source, mint, dest, owner) = (key(), key(), key(), key());
+        let ixs = send(json!({
+            "source": source.to_string(),
+            "destination": dest.to_string(),
+            "mint": mint.to_string(),
+            "owner": owner.to_string(),
+            "amount": 1000,
+            "decimals": 2,
+            "fee": 5,
+            "tokenProgram": "spl-token-2022",
+        }))
+        .await
+        .unwrap();
+
+        assert_eq!(ixs[0].program_id, spl_token_2022::id());
+        assert_eq!(keys(&ixs[0]), vec![source, mint, dest, owner]);
+        assert_eq!(
+            ixs[0].data,
+            transfer_checked_with_fee(
+                &spl_token_2022::id(),
+                &source,
+                &mint,
+                &dest,
+                &owner,
+                &[],
+                1000,
+                2,
+                5
+            )
+            .unwrap()
+            .data
+        );
+    }
+}
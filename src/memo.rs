@@ -0,0 +1,40 @@
+// memo.rs
+
+use axum::Json;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::compute_budget::ComputeBudgetOptions;
+use crate::{ApiResponse, ApiResult, BuilderOutput, InstructionData, instruction_output};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoRequest {
+    memo: String,
+    /// Accounts that must sign the memo; the memo program checks each of them is a signer.
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn create_memo(
+    Json(payload): Json<MemoRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    if payload.memo.is_empty() {
+        return Err(Json(ApiResponse::err("Memo must not be empty")));
+    }
+    let signers = payload
+        .signers
+        .iter()
+        .map(|s| Pubkey::from_str(s))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Json(ApiResponse::err("Invalid signer pubkey")))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(spl_memo::build_memo(payload.memo.as_bytes(), &signer_refs));
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
@@ -0,0 +1,190 @@
+// nonce.rs
+
+use axum::Json;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::Deserialize;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    nonce::state::{State, Versions},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+};
+use std::str::FromStr;
+
+use crate::compute_budget::ComputeBudgetOptions;
+use crate::{ApiResponse, ApiResult, BuilderOutput, InstructionData, instruction_output};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateNonceRequest {
+    from: String,
+    nonce: String,
+    authority: String,
+    /// Defaults to the rent-exempt minimum for a nonce account.
+    lamports: Option<u64>,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn create_nonce(
+    Json(payload): Json<CreateNonceRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let from =
+        Pubkey::from_str(&payload.from).map_err(|_| Json(ApiResponse::err("Invalid from")))?;
+    let nonce =
+        Pubkey::from_str(&payload.nonce).map_err(|_| Json(ApiResponse::err("Invalid nonce")))?;
+    let authority = Pubkey::from_str(&payload.authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid authority")))?;
+    let lamports = payload
+        .lamports
+        .unwrap_or_else(|| Rent::default().minimum_balance(State::size()));
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.extend(system_instruction::create_nonce_account(
+        &from, &nonce, &authority, lamports,
+    ));
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdvanceNonceRequest {
+    nonce: String,
+    authority: String,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn advance_nonce(
+    Json(payload): Json<AdvanceNonceRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let nonce =
+        Pubkey::from_str(&payload.nonce).map_err(|_| Json(ApiResponse::err("Invalid nonce")))?;
+    let authority = Pubkey::from_str(&payload.authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid authority")))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(system_instruction::advance_nonce_account(
+        &nonce, &authority,
+    ));
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawNonceRequest {
+    nonce: String,
+    authority: String,
+    to: String,
+    lamports: u64,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn withdraw_nonce(
+    Json(payload): Json<WithdrawNonceRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let nonce =
+        Pubkey::from_str(&payload.nonce).map_err(|_| Json(ApiResponse::err("Invalid nonce")))?;
+    let authority = Pubkey::from_str(&payload.authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid authority")))?;
+    let to = Pubkey::from_str(&payload.to).map_err(|_| Json(ApiResponse::err("Invalid to")))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(system_instruction::withdraw_nonce_account(
+        &nonce,
+        &authority,
+        &to,
+        payload.lamports,
+    ));
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizeNonceRequest {
+    nonce: String,
+    authority: String,
+    new_authority: String,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn authorize_nonce(
+    Json(payload): Json<AuthorizeNonceRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let nonce =
+        Pubkey::from_str(&payload.nonce).map_err(|_| Json(ApiResponse::err("Invalid nonce")))?;
+    let authority = Pubkey::from_str(&payload.authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid authority")))?;
+    let new_authority = Pubkey::from_str(&payload.new_authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid new authority")))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(system_instruction::authorize_nonce_account(
+        &nonce,
+        &authority,
+        &new_authority,
+    ));
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+/// Durable nonce settings for `/transaction/build`. The stored nonce is taken from `value`, or
+/// read out of the nonce account's base64 `accountData` as fetched from a cluster.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceOptions {
+    account: String,
+    authority: String,
+    value: Option<String>,
+    account_data: Option<String>,
+}
+
+impl NonceOptions {
+    /// Returns the durable blockhash and the `advance_nonce_account` instruction that must lead
+    /// the transaction.
+    pub fn resolve(&self) -> Result<(Hash, Instruction), String> {
+        let account =
+            Pubkey::from_str(&self.account).map_err(|_| "Invalid nonce account".to_string())?;
+        let authority =
+            Pubkey::from_str(&self.authority).map_err(|_| "Invalid nonce authority".to_string())?;
+
+        let blockhash = match (&self.value, &self.account_data) {
+            (Some(value), None) => {
+                Hash::from_str(value).map_err(|_| "Invalid nonce value".to_string())?
+            }
+            (None, Some(data)) => {
+                let bytes = BASE64
+                    .decode(data)
+                    .map_err(|_| "Invalid nonce account data encoding".to_string())?;
+                let versions: Versions = bincode::deserialize(&bytes)
+                    .map_err(|_| "Invalid nonce account data".to_string())?;
+                let Versions::Current(state) = versions else {
+                    return Err("Nonce account must be upgraded before use".to_string());
+                };
+                let State::Initialized(data) = *state else {
+                    return Err("Nonce account is not initialized".to_string());
+                };
+                if data.authority != authority {
+                    return Err(format!(
+                        "Nonce authority mismatch: account is controlled by {}",
+                        data.authority
+                    ));
+                }
+                data.blockhash()
+            }
+            _ => return Err("Provide exactly one of nonce value or accountData".to_string()),
+        };
+
+        Ok((
+            blockhash,
+            system_instruction::advance_nonce_account(&account, &authority),
+        ))
+    }
+}
@@ -0,0 +1,1009 @@
+// simulate.rs
+
+use axum::Json;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use solana_sdk::{
+    account_info::AccountInfo,
+    clock::Clock,
+    compute_budget,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_error::{PrintProgramError, ProgramError},
+    program_pack::Pack,
+    program_stubs::{SyscallStubs, set_syscall_stubs},
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::{self, MAX_PERMITTED_DATA_LENGTH, SystemInstruction},
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    state::{Account as TokenAccount, Mint},
+};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use std::sync::Once;
+
+use crate::transaction::parse_instructions;
+use crate::{ApiResponse, ApiResult, InstructionData};
+
+thread_local! {
+    static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    /// The program a processor last tried to invoke, which fails its instruction.
+    static REFUSED_INVOKE: Cell<Option<Pubkey>> = const { Cell::new(None) };
+}
+
+fn log(message: String) {
+    LOGS.with(|logs| logs.borrow_mut().push(message));
+}
+
+/// Routes `msg!` output and sysvar reads made by natively compiled programs into the simulator.
+/// Logs are collected per thread; a simulation runs start to finish on one thread.
+struct SimulatorStubs;
+
+impl SyscallStubs for SimulatorStubs {
+    fn sol_log(&self, message: &str) {
+        log(format!("Program log: {message}"));
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: `Rent::get` hands us a pointer to an uninitialized `Rent`.
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        // SAFETY: as above, for `Clock::get`. The ledger has no slots, so time stands at zero.
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        SUCCESS
+    }
+
+    /// The default stub reports success without running anything, which would let e.g. a
+    /// transfer hook pass unexecuted.
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        REFUSED_INVOKE.with(|refused| refused.set(Some(instruction.program_id)));
+        Err(ProgramError::InvalidArgument)
+    }
+}
+
+static INSTALL_STUBS: Once = Once::new();
+
+#[derive(Clone, Default, PartialEq)]
+pub struct SimAccount {
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+    executable: bool,
+}
+
+/// An in-memory ledger that executes System, SPL Token, Associated Token Account, Memo and
+/// Compute Budget instructions.
+/// Every declared signer is treated as having signed.
+#[derive(Default)]
+pub struct Bank {
+    accounts: BTreeMap<Pubkey, SimAccount>,
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        INSTALL_STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(SimulatorStubs));
+        });
+        Self::default()
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: SimAccount) {
+        self.accounts.insert(key, account);
+    }
+
+    /// Missing accounts read as empty system accounts; the rent sysvar and both programs'
+    /// native mints, present on every cluster, are synthesized.
+    fn account(&self, key: &Pubkey) -> SimAccount {
+        if let Some(account) = self.accounts.get(key) {
+            return account.clone();
+        }
+        if *key == spl_token::native_mint::id() {
+            return native_mint(spl_token::id());
+        }
+        if *key == spl_token_2022::native_mint::id() {
+            return native_mint(spl_token_2022::id());
+        }
+        if *key == sysvar::rent::id() {
+            return SimAccount {
+                lamports: 1,
+                data: bincode::serialize(&Rent::default()).unwrap_or_default(),
+                owner: sysvar::id(),
+                executable: false,
+            };
+        }
+        SimAccount::default()
+    }
+
+    fn store(&mut self, key: Pubkey, account: SimAccount) {
+        if sysvar::is_sysvar_id(&key) {
+            return;
+        }
+        // Synthesized accounts only enter the ledger once something changes them.
+        if !self.accounts.contains_key(&key) && account == self.account(&key) {
+            return;
+        }
+        if account.lamports == 0 && account.data.is_empty() {
+            self.accounts.remove(&key);
+        } else {
+            self.accounts.insert(key, account);
+        }
+    }
+
+    /// Executes `instructions` atomically, leaving the bank untouched if any of them fails.
+    /// On failure returns the failing instruction index (if one failed) and the error.
+    pub fn process_transaction(
+        &mut self,
+        instructions: &[Instruction],
+    ) -> Result<(), (Option<usize>, String)> {
+        let snapshot = self.accounts.clone();
+        for (index, ix) in instructions.iter().enumerate() {
+            if let Err(e) = self.process_instruction(ix, 1) {
+                self.accounts = snapshot;
+                return Err((Some(index), e));
+            }
+        }
+        // Accounts left without lamports, such as closed token accounts, cease to exist.
+        self.accounts.retain(|_, account| account.lamports > 0);
+        if let Err(e) = self.check_rent_state(&snapshot, instructions) {
+            self.accounts = snapshot;
+            return Err((None, e));
+        }
+        Ok(())
+    }
+
+    /// `depth` is 1 for a transaction's own instructions and grows with each nested invoke.
+    fn process_instruction(&mut self, ix: &Instruction, depth: usize) -> Result<(), String> {
+        log(format!("Program {} invoke [{depth}]", ix.program_id));
+        let keys = unique_keys(ix);
+        let pre: Vec<SimAccount> = keys.iter().map(|k| self.account(k)).collect();
+
+        let result = if ix.program_id == system_program::id() {
+            self.process_system(ix)
+        } else if ix.program_id == spl_token::id() || ix.program_id == spl_token_2022::id() {
+            self.process_token(ix, &keys)
+        } else if ix.program_id == spl_associated_token_account::id() {
+            self.process_associated_token(ix, depth)
+        } else if ix.program_id == spl_memo::id() {
+            process_memo(ix)
+        } else if ix.program_id == compute_budget::id() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Program {} is not supported by the simulator",
+                ix.program_id
+            ))
+        }
+        .and_then(|()| self.verify_effects(ix, &keys, &pre));
+
+        match &result {
+            Ok(()) => log(format!("Program {} success", ix.program_id)),
+            Err(e) => log(format!("Program {} failed: {e}", ix.program_id)),
+        }
+        result
+    }
+
+    /// Enforces the runtime's account rules on what an instruction changed.
+    fn verify_effects(
+        &self,
+        ix: &Instruction,
+        keys: &[Pubkey],
+        pre: &[SimAccount],
+    ) -> Result<(), String> {
+        let mut pre_total: u128 = 0;
+        let mut post_total: u128 = 0;
+        for (key, before) in keys.iter().zip(pre) {
+            let after = self.account(key);
+            pre_total += before.lamports as u128;
+            post_total += after.lamports as u128;
+            if after == *before {
+                continue;
+            }
+            let writable = ix
+                .accounts
+                .iter()
+                .any(|m| m.pubkey == *key && m.is_writable);
+            if !writable {
+                return Err(format!("Instruction modified read-only account {key}"));
+            }
+            // The ATA program changes accounts only through the instructions it invokes, which
+            // were each verified against their own program as they ran.
+            let owned = before.owner == ix.program_id
+                || ix.program_id == spl_associated_token_account::id();
+            if !owned && (after.data != before.data || after.owner != before.owner) {
+                return Err(format!(
+                    "Instruction modified data of account {key} not owned by the program"
+                ));
+            }
+            if !owned && after.lamports < before.lamports {
+                return Err(format!(
+                    "Instruction spent from account {key} not owned by the program"
+                ));
+            }
+        }
+        if pre_total != post_total {
+            return Err("Sum of account balances changed".to_string());
+        }
+        Ok(())
+    }
+
+    /// Rejects transactions that leave a written account holding too few lamports for its size.
+    fn check_rent_state(
+        &self,
+        before: &BTreeMap<Pubkey, SimAccount>,
+        instructions: &[Instruction],
+    ) -> Result<(), String> {
+        let rent = Rent::default();
+        let written: BTreeSet<Pubkey> = instructions
+            .iter()
+            .flat_map(|ix| ix.accounts.iter())
+            .filter(|m| m.is_writable)
+            .map(|m| m.pubkey)
+            .collect();
+        for key in written {
+            let Some(after) = self.accounts.get(&key) else {
+                continue;
+            };
+            let was_rent_paying = before.get(&key).is_some_and(|b| {
+                b.data.len() == after.data.len() && !rent.is_exempt(b.lamports, b.data.len())
+            });
+            if !rent.is_exempt(after.lamports, after.data.len()) && !was_rent_paying {
+                return Err(format!(
+                    "Transaction results in an account ({key}) with insufficient funds for rent"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn process_system(&mut self, ix: &Instruction) -> Result<(), String> {
+        let instruction: SystemInstruction =
+            limited_deserialize(&ix.data).map_err(|_| "invalid instruction data".to_string())?;
+        let meta = |i: usize| {
+            ix.accounts
+                .get(i)
+                .ok_or_else(|| "not enough account keys".to_string())
+        };
+        let require_signer = |i: usize| -> Result<Pubkey, String> {
+            let m = meta(i)?;
+            if m.is_signer {
+                Ok(m.pubkey)
+            } else {
+                Err(format!("{} must sign", m.pubkey))
+            }
+        };
+
+        match instruction {
+            SystemInstruction::Transfer { lamports } => {
+                let from_key = require_signer(0)?;
+                let to_key = meta(1)?.pubkey;
+                let mut from = self.account(&from_key);
+                if !from.data.is_empty() {
+                    log("Transfer: `from` must not carry data".to_string());
+                    return Err("invalid argument".to_string());
+                }
+                if from.lamports < lamports {
+                    log(format!(
+                        "Transfer: insufficient lamports {}, need {lamports}",
+                        from.lamports
+                    ));
+                    return Err("custom program error: 0x1".to_string());
+                }
+                from.lamports -= lamports;
+                self.store(from_key, from);
+                let mut to = self.account(&to_key);
+                to.lamports = to
+                    .lamports
+                    .checked_add(lamports)
+                    .ok_or("arithmetic overflow")?;
+                self.store(to_key, to);
+            }
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => {
+                let from_key = require_signer(0)?;
+                let to_key = require_signer(1)?;
+                let space = checked_space(space)?;
+                let existing = self.account(&to_key);
+                if existing.lamports > 0
+                    || !existing.data.is_empty()
+                    || existing.owner != system_program::id()
+                {
+                    log(format!("Create Account: account {to_key} already in use"));
+                    return Err("custom program error: 0x0".to_string());
+                }
+                let mut from = self.account(&from_key);
+                if from.lamports < lamports {
+                    log(format!(
+                        "Transfer: insufficient lamports {}, need {lamports}",
+                        from.lamports
+                    ));
+                    return Err("custom program error: 0x1".to_string());
+                }
+                from.lamports -= lamports;
+                self.store(from_key, from);
+                self.store(
+                    to_key,
+                    SimAccount {
+                        lamports,
+                        data: vec![0; space],
+                        owner,
+                        executable: false,
+                    },
+                );
+            }
+            SystemInstruction::Assign { owner } => {
+                let key = require_signer(0)?;
+                let mut account = self.account(&key);
+                if account.owner != owner {
+                    if account.owner != system_program::id() {
+                        return Err("incorrect program id for instruction".to_string());
+                    }
+                    account.owner = owner;
+                    self.store(key, account);
+                }
+            }
+            SystemInstruction::Allocate { space } => {
+                let key = require_signer(0)?;
+                let space = checked_space(space)?;
+                let mut account = self.account(&key);
+                if !account.data.is_empty() || account.owner != system_program::id() {
+                    log(format!("Allocate: account {key} already in use"));
+                    return Err("custom program error: 0x0".to_string());
+                }
+                account.data = vec![0; space];
+                self.store(key, account);
+            }
+            _ => return Err("System instruction not supported by the simulator".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Creates an associated token account the way the ATA program does, invoking the System
+    /// and token programs in turn. The program itself is not run: it relies on cross-program
+    /// invocation, which the syscall stubs cannot provide.
+    fn process_associated_token(&mut self, ix: &Instruction, depth: usize) -> Result<(), String> {
+        let idempotent = match ix.data.first() {
+            None | Some(0) => false,
+            Some(1) => true,
+            Some(_) => {
+                return Err(
+                    "Associated token instruction not supported by the simulator".to_string(),
+                );
+            }
+        };
+        let key = |i: usize| {
+            ix.accounts
+                .get(i)
+                .map(|m| m.pubkey)
+                .ok_or_else(|| "not enough account keys".to_string())
+        };
+        let (funder, address, wallet, mint, token_program) =
+            (key(0)?, key(1)?, key(2)?, key(3)?, key(5)?);
+        if token_program != spl_token::id() && token_program != spl_token_2022::id() {
+            return Err(ProgramError::IncorrectProgramId.to_string());
+        }
+        if address != get_associated_token_address_with_program_id(&wallet, &mint, &token_program) {
+            log(
+                "Program log: Error: Associated address does not match seed derivation".to_string(),
+            );
+            return Err(ProgramError::InvalidSeeds.to_string());
+        }
+
+        let existing = self.account(&address);
+        if idempotent
+            && existing.owner == token_program
+            && let Ok(account) = StateWithExtensions::<TokenAccount>::unpack(&existing.data)
+        {
+            if account.base.owner != wallet {
+                log(
+                    "Program log: Associated token account owner does not match address derivation"
+                        .to_string(),
+                );
+                return Err("custom program error: 0x0".to_string());
+            }
+            if account.base.mint != mint {
+                return Err(ProgramError::InvalidAccountData.to_string());
+            }
+            return Ok(());
+        }
+        if existing.owner != system_program::id() {
+            return Err(ProgramError::IllegalOwner.to_string());
+        }
+
+        // The funder's signature is passed on to the System Program.
+        if !ix.accounts[0].is_signer {
+            log(format!("{funder}'s signer privilege escalated"));
+            return Err(
+                "Cross-program invocation with unauthorized signer or writable account".to_string(),
+            );
+        }
+
+        // Token-2022 sizes the account for the mint's extensions; every ATA is immutably owned.
+        let mut extension_types = Vec::new();
+        if token_program == spl_token_2022::id() {
+            let mint_account = self.account(&mint);
+            let mint_state = StateWithExtensions::<Mint>::unpack(&mint_account.data)
+                .map_err(|e| e.to_string())?;
+            extension_types = ExtensionType::get_required_init_account_extensions(
+                &mint_state
+                    .get_extension_types()
+                    .map_err(|e| e.to_string())?,
+            );
+            extension_types.push(ExtensionType::ImmutableOwner);
+        }
+        let space = ExtensionType::try_calculate_account_len::<TokenAccount>(&extension_types)
+            .map_err(|e| e.to_string())?;
+
+        // An account already holding lamports is topped up rather than created.
+        let lamports = Rent::default().minimum_balance(space).max(1);
+        let mut invoked = Vec::new();
+        if existing.lamports > 0 {
+            let shortfall = lamports.saturating_sub(existing.lamports);
+            if shortfall > 0 {
+                invoked.push(system_instruction::transfer(&funder, &address, shortfall));
+            }
+            invoked.push(system_instruction::allocate(&address, space as u64));
+            invoked.push(system_instruction::assign(&address, &token_program));
+        } else {
+            invoked.push(system_instruction::create_account(
+                &funder,
+                &address,
+                lamports,
+                space as u64,
+                &token_program,
+            ));
+        }
+        invoked.push(
+            spl_token_2022::instruction::initialize_immutable_owner(&token_program, &address)
+                .map_err(|e| e.to_string())?,
+        );
+        invoked.push(
+            spl_token_2022::instruction::initialize_account3(
+                &token_program,
+                &address,
+                &mint,
+                &wallet,
+            )
+            .map_err(|e| e.to_string())?,
+        );
+        for inner in &invoked {
+            self.process_instruction(inner, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the real SPL Token or Token-2022 processor against the bank's accounts.
+    fn process_token(&mut self, ix: &Instruction, keys: &[Pubkey]) -> Result<(), String> {
+        let mut states: Vec<SimAccount> = keys.iter().map(|k| self.account(k)).collect();
+        let result = {
+            let infos: Vec<AccountInfo> = keys
+                .iter()
+                .zip(states.iter_mut())
+                .map(|(key, state)| {
+                    let is_signer = ix.accounts.iter().any(|m| m.pubkey == *key && m.is_signer);
+                    let is_writable = ix
+                        .accounts
+                        .iter()
+                        .any(|m| m.pubkey == *key && m.is_writable);
+                    let SimAccount {
+                        lamports,
+                        data,
+                        owner,
+                        executable,
+                    } = state;
+                    AccountInfo::new(
+                        key,
+                        is_signer,
+                        is_writable,
+                        lamports,
+                        data,
+                        owner,
+                        *executable,
+                        0,
+                    )
+                })
+                .collect();
+            // Duplicate metas share one `AccountInfo`, exactly as the runtime does.
+            let ix_infos: Vec<AccountInfo> = ix
+                .accounts
+                .iter()
+                .map(|m| {
+                    let position = keys.iter().position(|k| *k == m.pubkey).unwrap_or(0);
+                    infos[position].clone()
+                })
+                .collect();
+            if ix.program_id == spl_token::id() {
+                spl_token::processor::Processor::process(&ix.program_id, &ix_infos, &ix.data)
+                    .inspect_err(|e| e.print::<spl_token::error::TokenError>())
+            } else {
+                spl_token_2022::processor::Processor::process(&ix.program_id, &ix_infos, &ix.data)
+                    .inspect_err(|e| e.print::<spl_token_2022::error::TokenError>())
+            }
+        };
+        // Checked before the result, in case the processor ignored the failed invoke.
+        if let Some(program) = REFUSED_INVOKE.with(|refused| refused.take()) {
+            return Err(format!(
+                "Cross-program invocation of {program} is not supported by the simulator"
+            ));
+        }
+        if let Err(e) = result {
+            return Err(e.to_string());
+        }
+        for (key, state) in keys.iter().zip(states) {
+            self.store(*key, state);
+        }
+        Ok(())
+    }
+}
+
+fn native_mint(owner: Pubkey) -> SimAccount {
+    let mut data = vec![0; Mint::LEN];
+    Mint {
+        decimals: spl_token::native_mint::DECIMALS,
+        is_initialized: true,
+        ..Mint::default()
+    }
+    .pack_into_slice(&mut data);
+    SimAccount {
+        lamports: Rent::default().minimum_balance(Mint::LEN),
+        data,
+        owner,
+        executable: false,
+    }
+}
+
+/// Refuses sizes the runtime would, before anything is allocated for them.
+fn checked_space(space: u64) -> Result<usize, String> {
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        log(format!(
+            "Allocate: requested {space}, max allowed {MAX_PERMITTED_DATA_LENGTH}"
+        ));
+        return Err("custom program error: 0x3".to_string());
+    }
+    Ok(space as usize)
+}
+
+fn process_memo(ix: &Instruction) -> Result<(), String> {
+    for meta in &ix.accounts {
+        if !meta.is_signer {
+            log(format!("Expected signature from {}", meta.pubkey));
+            return Err("missing required signature for instruction".to_string());
+        }
+    }
+    let memo = std::str::from_utf8(&ix.data).map_err(|_| "invalid instruction data")?;
+    log(format!(
+        "Program log: Memo (len {}): {memo:?}",
+        ix.data.len()
+    ));
+    Ok(())
+}
+
+/// Keys of the instruction's accounts in first-seen order.
+fn unique_keys(ix: &Instruction) -> Vec<Pubkey> {
+    let mut keys = Vec::with_capacity(ix.accounts.len());
+    for meta in &ix.accounts {
+        if !keys.contains(&meta.pubkey) {
+            keys.push(meta.pubkey);
+        }
+    }
+    keys
+}
+
+fn extension_names(types: Vec<ExtensionType>) -> Vec<String> {
+    types.iter().map(|ty| format!("{ty:?}")).collect()
+}
+
+fn parse_token_state(account: &SimAccount) -> Option<Value> {
+    if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+        return None;
+    }
+    if let Ok(state) = StateWithExtensions::<Mint>::unpack(&account.data) {
+        let mint = state.base;
+        return Some(json!({
+            "type": "mint",
+            "extensions": extension_names(state.get_extension_types().ok()?),
+            "mintAuthority": Option::<Pubkey>::from(mint.mint_authority).map(|k| k.to_string()),
+            "supply": mint.supply,
+            "decimals": mint.decimals,
+            "freezeAuthority": Option::<Pubkey>::from(mint.freeze_authority).map(|k| k.to_string()),
+        }));
+    }
+    if let Ok(state) = StateWithExtensions::<TokenAccount>::unpack(&account.data) {
+        let token = state.base;
+        return Some(json!({
+            "type": "account",
+            "extensions": extension_names(state.get_extension_types().ok()?),
+            "mint": token.mint.to_string(),
+            "owner": token.owner.to_string(),
+            "amount": token.amount,
+            "delegate": Option::<Pubkey>::from(token.delegate).map(|k| k.to_string()),
+            "delegatedAmount": token.delegated_amount,
+            "state": format!("{:?}", token.state),
+            "isNative": token.is_native.is_some(),
+            "closeAuthority": Option::<Pubkey>::from(token.close_authority).map(|k| k.to_string()),
+        }));
+    }
+    None
+}
+
+#[derive(Deserialize)]
+pub struct AccountStateInput {
+    pubkey: String,
+    lamports: u64,
+    /// Base64 account data; empty by default.
+    #[serde(default)]
+    data: String,
+    /// Defaults to the System Program.
+    owner: Option<String>,
+    #[serde(default)]
+    executable: bool,
+}
+
+impl AccountStateInput {
+    fn to_account(&self) -> Result<(Pubkey, SimAccount), String> {
+        let key = Pubkey::from_str(&self.pubkey)
+            .map_err(|_| format!("Invalid pubkey {}", self.pubkey))?;
+        let owner = match &self.owner {
+            Some(owner) => {
+                Pubkey::from_str(owner).map_err(|_| format!("Invalid owner for {key}"))?
+            }
+            None => system_program::id(),
+        };
+        let data = BASE64
+            .decode(&self.data)
+            .map_err(|_| format!("Invalid data for {key}"))?;
+        Ok((
+            key,
+            SimAccount {
+                lamports: self.lamports,
+                data,
+                owner,
+                executable: self.executable,
+            },
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SimulateRequest {
+    #[serde(default)]
+    accounts: Vec<AccountStateInput>,
+    instructions: Vec<InstructionData>,
+}
+
+#[derive(Serialize)]
+struct AccountStateInfo {
+    pubkey: String,
+    lamports: u64,
+    owner: String,
+    data: String,
+    executable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parsed: Option<Value>,
+}
+
+#[derive(Serialize)]
+pub struct SimulationData {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failed_instruction: Option<usize>,
+    logs: Vec<String>,
+    accounts: Vec<AccountStateInfo>,
+}
+
+pub async fn simulate(Json(payload): Json<SimulateRequest>) -> ApiResult<SimulationData> {
+    let instructions =
+        parse_instructions(&payload.instructions).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let mut bank = Bank::new();
+    for input in &payload.accounts {
+        let (key, account) = input.to_account().map_err(|e| Json(ApiResponse::err(&e)))?;
+        bank.set_account(key, account);
+    }
+
+    LOGS.with(|logs| logs.borrow_mut().clear());
+    let result = bank.process_transaction(&instructions);
+    let logs = LOGS.with(|logs| logs.take());
+    let (error, failed_instruction) = match result {
+        Ok(()) => (None, None),
+        Err((index, e)) => (Some(e), index),
+    };
+
+    Ok(Json(ApiResponse::ok(SimulationData {
+        success: error.is_none(),
+        error,
+        failed_instruction,
+        logs,
+        accounts: bank
+            .accounts
+            .iter()
+            .map(|(key, account)| AccountStateInfo {
+                pubkey: key.to_string(),
+                lamports: account.lamports,
+                owner: account.owner.to_string(),
+                data: BASE64.encode(&account.data),
+                executable: account.executable,
+                parsed: parse_token_state(account),
+            })
+            .collect(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{hash::hash, instruction::AccountMeta};
+    use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+    use spl_token::error::TokenError;
+    use spl_token_2022::extension::transfer_hook;
+    use spl_token_2022::instruction::{
+        initialize_account3, initialize_mint2, mint_to, transfer_checked,
+    };
+
+    const SOL: u64 = 1_000_000_000;
+
+    fn funded(bank: &mut Bank, lamports: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        bank.set_account(
+            key,
+            SimAccount {
+                lamports,
+                ..SimAccount::default()
+            },
+        );
+        key
+    }
+
+    fn create_mint(payer: &Pubkey, mint: &Pubkey, authority: &Pubkey) -> Vec<Instruction> {
+        vec![
+            system_instruction::create_account(
+                payer,
+                mint,
+                Rent::default().minimum_balance(Mint::LEN),
+                Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            initialize_mint2(&spl_token::id(), mint, authority, None, 0).unwrap(),
+        ]
+    }
+
+    fn token_error(error: TokenError) -> String {
+        ProgramError::from(error).to_string()
+    }
+
+    fn token_amount(bank: &Bank, account: &Pubkey) -> u64 {
+        StateWithExtensions::<TokenAccount>::unpack(&bank.account(account).data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    #[test]
+    fn transfer_beyond_balance_fails() {
+        let mut bank = Bank::new();
+        let from = funded(&mut bank, SOL);
+        let to = Pubkey::new_unique();
+
+        let result = bank.process_transaction(&[system_instruction::transfer(&from, &to, SOL + 1)]);
+
+        assert_eq!(
+            result,
+            Err((Some(0), "custom program error: 0x1".to_string()))
+        );
+        assert_eq!(bank.account(&from).lamports, SOL);
+    }
+
+    #[test]
+    fn mint_to_with_wrong_authority_fails() {
+        let mut bank = Bank::new();
+        let payer = funded(&mut bank, SOL);
+        let (mint, authority, impostor) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let ata = get_associated_token_address_with_program_id(&payer, &mint, &spl_token::id());
+        let mut setup = create_mint(&payer, &mint, &authority);
+        setup.push(create_associated_token_account_idempotent(
+            &payer,
+            &payer,
+            &mint,
+            &spl_token::id(),
+        ));
+        bank.process_transaction(&setup).unwrap();
+
+        let result =
+            bank.process_transaction(&[
+                mint_to(&spl_token::id(), &mint, &ata, &impostor, &[], 10).unwrap()
+            ]);
+
+        assert_eq!(
+            result,
+            Err((Some(0), token_error(TokenError::OwnerMismatch)))
+        );
+        assert_eq!(token_amount(&bank, &ata), 0);
+
+        bank.process_transaction(&[
+            mint_to(&spl_token::id(), &mint, &ata, &authority, &[], 10).unwrap()
+        ])
+        .unwrap();
+        assert_eq!(token_amount(&bank, &ata), 10);
+    }
+
+    #[test]
+    fn token_account_for_uninitialized_mint_fails() {
+        let mut bank = Bank::new();
+        let payer = funded(&mut bank, SOL);
+        let (mint, account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let allocate_mint = create_mint(&payer, &mint, &payer).remove(0);
+        let rent = Rent::default().minimum_balance(TokenAccount::LEN);
+
+        let result = bank.process_transaction(&[
+            allocate_mint,
+            system_instruction::create_account(
+                &payer,
+                &account,
+                rent,
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            initialize_account3(&spl_token::id(), &account, &mint, &payer).unwrap(),
+        ]);
+
+        assert_eq!(result, Err((Some(2), token_error(TokenError::InvalidMint))));
+    }
+
+    #[test]
+    fn failing_instruction_rolls_back_earlier_ones() {
+        let mut bank = Bank::new();
+        let from = funded(&mut bank, SOL);
+        let to = funded(&mut bank, SOL);
+        let before = bank.accounts.clone();
+
+        let result = bank.process_transaction(&[
+            system_instruction::transfer(&from, &to, SOL / 2),
+            system_instruction::transfer(&from, &to, SOL),
+        ]);
+
+        assert_eq!(result.unwrap_err().0, Some(1));
+        assert!(bank.accounts == before);
+    }
+
+    #[test]
+    fn rent_state_check_rejects_underfunded_new_accounts() {
+        let mut bank = Bank::new();
+        let from = funded(&mut bank, SOL);
+        let to = Pubkey::new_unique();
+        let before = bank.accounts.clone();
+
+        let (index, error) = bank
+            .process_transaction(&[system_instruction::transfer(&from, &to, 1)])
+            .unwrap_err();
+
+        assert_eq!(index, None);
+        assert!(error.contains("insufficient funds for rent"), "{error}");
+        assert!(bank.accounts == before);
+
+        let exempt = Rent::default().minimum_balance(0);
+        bank.process_transaction(&[system_instruction::transfer(&from, &to, exempt)])
+            .unwrap();
+        assert_eq!(bank.account(&to).lamports, exempt);
+    }
+
+    #[test]
+    fn transfer_hook_invocation_is_refused() {
+        let program = spl_token_2022::id();
+        let mut bank = Bank::new();
+        let payer = funded(&mut bank, SOL);
+        let (mint, recipient, hook) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let space =
+            ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook])
+                .unwrap();
+        let source = get_associated_token_address_with_program_id(&payer, &mint, &program);
+        let destination = get_associated_token_address_with_program_id(&recipient, &mint, &program);
+        bank.process_transaction(&[
+            system_instruction::create_account(
+                &payer,
+                &mint,
+                Rent::default().minimum_balance(space),
+                space as u64,
+                &program,
+            ),
+            transfer_hook::instruction::initialize(&program, &mint, None, Some(hook)).unwrap(),
+            initialize_mint2(&program, &mint, &payer, None, 0).unwrap(),
+            create_associated_token_account_idempotent(&payer, &payer, &mint, &program),
+            create_associated_token_account_idempotent(&payer, &recipient, &mint, &program),
+            mint_to(&program, &mint, &source, &payer, &[], 100).unwrap(),
+        ])
+        .unwrap();
+
+        // The hook's validation account, holding an empty list of extra accounts: the execute
+        // discriminator, the entry length and a zero count.
+        let validation =
+            Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], &hook).0;
+        let mut data = hash(b"spl-transfer-hook-interface:execute").to_bytes()[..8].to_vec();
+        data.extend(4u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        bank.set_account(
+            validation,
+            SimAccount {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: hook,
+                executable: false,
+            },
+        );
+        let mut transfer =
+            transfer_checked(&program, &source, &mint, &destination, &payer, &[], 10, 0).unwrap();
+        transfer
+            .accounts
+            .push(AccountMeta::new_readonly(validation, false));
+        transfer
+            .accounts
+            .push(AccountMeta::new_readonly(hook, false));
+
+        let (index, error) = bank.process_transaction(&[transfer]).unwrap_err();
+
+        assert_eq!(index, Some(0));
+        assert!(error.contains("not supported by the simulator"), "{error}");
+        assert_eq!(token_amount(&bank, &source), 100);
+    }
+
+    #[test]
+    fn oversized_allocation_is_refused() {
+        let mut bank = Bank::new();
+        let key = funded(&mut bank, SOL);
+
+        let result = bank.process_transaction(&[system_instruction::allocate(&key, 1 << 62)]);
+
+        assert_eq!(
+            result,
+            Err((Some(0), "custom program error: 0x3".to_string()))
+        );
+    }
+
+    #[test]
+    fn associated_token_account_is_created_once() {
+        for (program, mint) in [
+            (spl_token::id(), spl_token::native_mint::id()),
+            (spl_token_2022::id(), spl_token_2022::native_mint::id()),
+        ] {
+            let mut bank = Bank::new();
+            let payer = funded(&mut bank, SOL);
+            let wallet = Pubkey::new_unique();
+            let create =
+                create_associated_token_account_idempotent(&payer, &wallet, &mint, &program);
+
+            bank.process_transaction(&[create.clone(), create]).unwrap();
+
+            let ata = get_associated_token_address_with_program_id(&wallet, &mint, &program);
+            let account = bank.account(&ata);
+            assert_eq!(account.owner, program);
+            let state = StateWithExtensions::<TokenAccount>::unpack(&account.data).unwrap();
+            assert_eq!(state.base.owner, wallet);
+            assert_eq!(state.base.mint, mint);
+        }
+    }
+}
@@ -0,0 +1,910 @@
+// token.rs
+
+use axum::Json;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    hash::Hash, instruction::Instruction, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+    system_instruction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_token_2022::instruction::{AuthorityType, MAX_SIGNERS};
+use spl_token_2022::state::{Account, Multisig};
+use std::str::FromStr;
+
+use crate::batch::{BatchConstraints, PlanTransactionsData, plan_transactions_for};
+use crate::compute_budget::ComputeBudgetOptions;
+use crate::extensions;
+use crate::limits::MAX_COMPUTE_UNIT_LIMIT;
+use crate::{ApiResponse, ApiResult, BuilderOutput, InstructionData, instruction_output};
+
+/// Which token program an instruction targets. Accepts the program name or its address.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum TokenProgram {
+    #[default]
+    #[serde(
+        rename = "spl-token",
+        alias = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+    )]
+    Token,
+    #[serde(
+        rename = "spl-token-2022",
+        alias = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"
+    )]
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn id(self) -> Pubkey {
+        match self {
+            TokenProgram::Token => spl_token::id(),
+            TokenProgram::Token2022 => spl_token_2022::id(),
+        }
+    }
+
+    /// The wrapped SOL mint, which differs between the two programs.
+    pub fn native_mint(self) -> Pubkey {
+        match self {
+            TokenProgram::Token => spl_token::native_mint::id(),
+            TokenProgram::Token2022 => spl_token_2022::native_mint::id(),
+        }
+    }
+}
+
+/// Where tokens are sent: either an explicit token account, or a wallet whose associated
+/// token account the server derives (and optionally creates) for the mint.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenDestination {
+    destination: Option<String>,
+    destination_owner: Option<String>,
+    /// Prepends an idempotent ATA creation for `destinationOwner`.
+    #[serde(default)]
+    create_destination: bool,
+    /// Funds the ATA creation; defaults to the signing authority of the request.
+    payer: Option<String>,
+}
+
+impl TokenDestination {
+    /// Returns the destination token account plus any instructions needed to create it.
+    pub fn resolve(
+        &self,
+        mint: &Pubkey,
+        token_program: TokenProgram,
+        default_payer: &Pubkey,
+    ) -> Result<(Pubkey, Vec<Instruction>), String> {
+        match (&self.destination, &self.destination_owner) {
+            (Some(destination), None) => {
+                if self.create_destination {
+                    return Err("createDestination requires destinationOwner".to_string());
+                }
+                let destination =
+                    Pubkey::from_str(destination).map_err(|_| "Invalid destination".to_string())?;
+                Ok((destination, Vec::new()))
+            }
+            (None, Some(wallet)) => {
+                let wallet = Pubkey::from_str(wallet)
+                    .map_err(|_| "Invalid destination owner".to_string())?;
+                let ata = get_associated_token_address_with_program_id(
+                    &wallet,
+                    mint,
+                    &token_program.id(),
+                );
+                let mut setup = Vec::new();
+                if self.create_destination {
+                    let payer = match &self.payer {
+                        Some(payer) => {
+                            Pubkey::from_str(payer).map_err(|_| "Invalid payer".to_string())?
+                        }
+                        None => *default_payer,
+                    };
+                    setup.push(create_associated_token_account_idempotent(
+                        &payer,
+                        &wallet,
+                        mint,
+                        &token_program.id(),
+                    ));
+                }
+                Ok((ata, setup))
+            }
+            _ => Err("Provide exactly one of destination or destinationOwner".to_string()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssociatedTokenAddressRequest {
+    wallet: String,
+    mint: String,
+    #[serde(default)]
+    token_program: TokenProgram,
+}
+
+#[derive(Serialize)]
+pub struct AssociatedTokenAddressData {
+    address: String,
+    wallet: String,
+    mint: String,
+    token_program: String,
+}
+
+pub async fn associated_token_address(
+    Json(payload): Json<AssociatedTokenAddressRequest>,
+) -> ApiResult<AssociatedTokenAddressData> {
+    let wallet = Pubkey::from_str(&payload.wallet)
+        .map_err(|_| Json(ApiResponse::err("Invalid wallet pubkey")))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+    let program_id = payload.token_program.id();
+    let address = get_associated_token_address_with_program_id(&wallet, &mint, &program_id);
+
+    Ok(Json(ApiResponse::ok(AssociatedTokenAddressData {
+        address: address.to_string(),
+        wallet: payload.wallet,
+        mint: payload.mint,
+        token_program: program_id.to_string(),
+    })))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAssociatedTokenAccountRequest {
+    payer: String,
+    wallet: String,
+    mint: String,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Builds `CreateIdempotent`, which succeeds even if the account already exists.
+pub async fn create_associated_token_account(
+    Json(payload): Json<CreateAssociatedTokenAccountRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let payer = Pubkey::from_str(&payload.payer)
+        .map_err(|_| Json(ApiResponse::err("Invalid payer pubkey")))?;
+    let wallet = Pubkey::from_str(&payload.wallet)
+        .map_err(|_| Json(ApiResponse::err("Invalid wallet pubkey")))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(create_associated_token_account_idempotent(
+        &payer,
+        &wallet,
+        &mint,
+        &payload.token_program.id(),
+    ));
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+/// Parses the co-signers of a multisig authority; empty for a single-key authority.
+pub fn parse_signers(signers: &[String]) -> Result<Vec<Pubkey>, String> {
+    signers
+        .iter()
+        .map(|s| Pubkey::from_str(s).map_err(|_| format!("Invalid signer pubkey: {s}")))
+        .collect()
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurnTokenRequest {
+    /// The token account to burn from.
+    account: String,
+    mint: String,
+    /// The account's owner or an approved delegate. For a multisig, its address.
+    authority: String,
+    /// Multisig members signing for `authority`.
+    #[serde(default)]
+    signers: Vec<String>,
+    amount: u64,
+    /// Required unless `checked` is false.
+    decimals: Option<u8>,
+    /// Defaults to `burn_checked`; set to false for the legacy unchecked `burn`.
+    checked: Option<bool>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn burn_token(
+    Json(payload): Json<BurnTokenRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let account = Pubkey::from_str(&payload.account)
+        .map_err(|_| Json(ApiResponse::err("Invalid token account pubkey")))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+    let authority = Pubkey::from_str(&payload.authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid authority pubkey")))?;
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let instr = if payload.checked.unwrap_or(true) {
+        let decimals = payload.decimals.ok_or_else(|| {
+            Json(ApiResponse::err(
+                "decimals is required for burn_checked; set checked to false for an unchecked burn",
+            ))
+        })?;
+        spl_token_2022::instruction::burn_checked(
+            &payload.token_program.id(),
+            &account,
+            &mint,
+            &authority,
+            &signer_refs,
+            payload.amount,
+            decimals,
+        )
+    } else {
+        spl_token_2022::instruction::burn(
+            &payload.token_program.id(),
+            &account,
+            &mint,
+            &authority,
+            &signer_refs,
+            payload.amount,
+        )
+    }
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproveRequest {
+    /// The token account whose tokens the delegate may move.
+    account: String,
+    delegate: String,
+    owner: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    amount: u64,
+    /// Required with `decimals` unless `checked` is false.
+    mint: Option<String>,
+    decimals: Option<u8>,
+    /// Defaults to `approve_checked`; set to false for the legacy unchecked `approve`.
+    checked: Option<bool>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Builds an approval letting `delegate` transfer or burn up to `amount` from `account`.
+/// A new approval replaces any existing delegate.
+pub async fn approve(
+    Json(payload): Json<ApproveRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let account = Pubkey::from_str(&payload.account)
+        .map_err(|_| Json(ApiResponse::err("Invalid token account pubkey")))?;
+    let delegate = Pubkey::from_str(&payload.delegate)
+        .map_err(|_| Json(ApiResponse::err("Invalid delegate pubkey")))?;
+    let owner = Pubkey::from_str(&payload.owner)
+        .map_err(|_| Json(ApiResponse::err("Invalid owner pubkey")))?;
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let instr = if payload.checked.unwrap_or(true) {
+        let (Some(mint), Some(decimals)) = (&payload.mint, payload.decimals) else {
+            return Err(Json(ApiResponse::err(
+                "mint and decimals are required for approve_checked; set checked to false for an unchecked approve",
+            )));
+        };
+        let mint = Pubkey::from_str(mint)
+            .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+        spl_token_2022::instruction::approve_checked(
+            &payload.token_program.id(),
+            &account,
+            &mint,
+            &delegate,
+            &owner,
+            &signer_refs,
+            payload.amount,
+            decimals,
+        )
+    } else {
+        spl_token_2022::instruction::approve(
+            &payload.token_program.id(),
+            &account,
+            &delegate,
+            &owner,
+            &signer_refs,
+            payload.amount,
+        )
+    }
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeRequest {
+    account: String,
+    owner: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn revoke(
+    Json(payload): Json<RevokeRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let account = Pubkey::from_str(&payload.account)
+        .map_err(|_| Json(ApiResponse::err("Invalid token account pubkey")))?;
+    let owner = Pubkey::from_str(&payload.owner)
+        .map_err(|_| Json(ApiResponse::err("Invalid owner pubkey")))?;
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let instr = spl_token_2022::instruction::revoke(
+        &payload.token_program.id(),
+        &account,
+        &owner,
+        &signer_refs,
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FreezeRequest {
+    account: String,
+    mint: String,
+    freeze_authority: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+type FreezeBuilder = fn(
+    &Pubkey,
+    &Pubkey,
+    &Pubkey,
+    &Pubkey,
+    &[&Pubkey],
+) -> Result<Instruction, solana_sdk::program_error::ProgramError>;
+
+fn freeze_or_thaw(
+    payload: FreezeRequest,
+    build: FreezeBuilder,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let account = Pubkey::from_str(&payload.account)
+        .map_err(|_| Json(ApiResponse::err("Invalid token account pubkey")))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+    let authority = Pubkey::from_str(&payload.freeze_authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid freeze authority pubkey")))?;
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let instr = build(
+        &payload.token_program.id(),
+        &account,
+        &mint,
+        &authority,
+        &signer_refs,
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+pub async fn freeze_account(
+    Json(payload): Json<FreezeRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    freeze_or_thaw(payload, spl_token_2022::instruction::freeze_account)
+}
+
+pub async fn thaw_account(
+    Json(payload): Json<FreezeRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    freeze_or_thaw(payload, spl_token_2022::instruction::thaw_account)
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum AuthorityKind {
+    MintTokens,
+    FreezeAccount,
+    AccountOwner,
+    CloseAccount,
+    // Token-2022 extension authorities, all set on the mint.
+    TransferFeeConfig,
+    WithheldWithdraw,
+    CloseMint,
+    InterestRate,
+    PermanentDelegate,
+    ConfidentialTransferMint,
+    TransferHookProgramId,
+    ConfidentialTransferFeeConfig,
+    MetadataPointer,
+    GroupPointer,
+    GroupMemberPointer,
+}
+
+impl AuthorityKind {
+    /// Whether the authority belongs to a Token-2022 extension, unknown to the classic program.
+    fn requires_token_2022(self) -> bool {
+        !matches!(
+            self,
+            AuthorityKind::MintTokens
+                | AuthorityKind::FreezeAccount
+                | AuthorityKind::AccountOwner
+                | AuthorityKind::CloseAccount
+        )
+    }
+}
+
+impl From<AuthorityKind> for AuthorityType {
+    fn from(kind: AuthorityKind) -> Self {
+        match kind {
+            AuthorityKind::MintTokens => AuthorityType::MintTokens,
+            AuthorityKind::FreezeAccount => AuthorityType::FreezeAccount,
+            AuthorityKind::AccountOwner => AuthorityType::AccountOwner,
+            AuthorityKind::CloseAccount => AuthorityType::CloseAccount,
+            AuthorityKind::TransferFeeConfig => AuthorityType::TransferFeeConfig,
+            AuthorityKind::WithheldWithdraw => AuthorityType::WithheldWithdraw,
+            AuthorityKind::CloseMint => AuthorityType::CloseMint,
+            AuthorityKind::InterestRate => AuthorityType::InterestRate,
+            AuthorityKind::PermanentDelegate => AuthorityType::PermanentDelegate,
+            AuthorityKind::ConfidentialTransferMint => AuthorityType::ConfidentialTransferMint,
+            AuthorityKind::TransferHookProgramId => AuthorityType::TransferHookProgramId,
+            AuthorityKind::ConfidentialTransferFeeConfig => {
+                AuthorityType::ConfidentialTransferFeeConfig
+            }
+            AuthorityKind::MetadataPointer => AuthorityType::MetadataPointer,
+            AuthorityKind::GroupPointer => AuthorityType::GroupPointer,
+            AuthorityKind::GroupMemberPointer => AuthorityType::GroupMemberPointer,
+        }
+    }
+}
+
+/// Distinguishes an explicit `null` from a missing field, so an authority is only ever
+/// removed on purpose.
+fn present<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<Option<String>>, D::Error> {
+    Option::deserialize(d).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAuthorityRequest {
+    /// The token account for account owner and close authorities, otherwise the mint.
+    account: String,
+    authority_type: AuthorityKind,
+    current_authority: String,
+    /// `null` removes the authority permanently.
+    #[serde(default, deserialize_with = "present")]
+    new_authority: Option<Option<String>>,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn set_authority(
+    Json(payload): Json<SetAuthorityRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let account = Pubkey::from_str(&payload.account)
+        .map_err(|_| Json(ApiResponse::err("Invalid account pubkey")))?;
+    let current = Pubkey::from_str(&payload.current_authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid current authority pubkey")))?;
+    let new_authority = match &payload.new_authority {
+        None => {
+            return Err(Json(ApiResponse::err(
+                "newAuthority is required; pass null to remove the authority",
+            )));
+        }
+        Some(None) => None,
+        Some(Some(key)) => Some(
+            Pubkey::from_str(key)
+                .map_err(|_| Json(ApiResponse::err("Invalid new authority pubkey")))?,
+        ),
+    };
+    if payload.authority_type.requires_token_2022()
+        && payload.token_program != TokenProgram::Token2022
+    {
+        return Err(Json(ApiResponse::err(
+            "Extension authorities require tokenProgram spl-token-2022",
+        )));
+    }
+    if new_authority.is_none() && matches!(payload.authority_type, AuthorityKind::AccountOwner) {
+        return Err(Json(ApiResponse::err(
+            "A token account's owner cannot be removed, only transferred",
+        )));
+    }
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let instr = spl_token_2022::instruction::set_authority(
+        &payload.token_program.id(),
+        &account,
+        new_authority.as_ref(),
+        payload.authority_type.into(),
+        &current,
+        &signer_refs,
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloseAccountRequest {
+    account: String,
+    /// Receives the reclaimed rent lamports.
+    destination: String,
+    /// The account owner, or its close authority if one is set.
+    owner: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Builds `CloseAccount`. The program rejects it unless the token balance is zero
+/// (wrapped SOL accounts excepted).
+pub async fn close_account(
+    Json(payload): Json<CloseAccountRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let account = Pubkey::from_str(&payload.account)
+        .map_err(|_| Json(ApiResponse::err("Invalid token account pubkey")))?;
+    let destination = Pubkey::from_str(&payload.destination)
+        .map_err(|_| Json(ApiResponse::err("Invalid destination pubkey")))?;
+    let owner = Pubkey::from_str(&payload.owner)
+        .map_err(|_| Json(ApiResponse::err("Invalid owner pubkey")))?;
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let instr = spl_token_2022::instruction::close_account(
+        &payload.token_program.id(),
+        &account,
+        &destination,
+        &owner,
+        &signer_refs,
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+pub struct CloseTarget {
+    account: String,
+    owner: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkCloseRequest {
+    accounts: Vec<CloseTarget>,
+    destination: String,
+    /// Defaults to `destination`.
+    fee_payer: Option<String>,
+    /// Defaults to the zero hash, for callers that only want the grouping.
+    recent_blockhash: Option<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Closes many accounts at once, packed into as few transactions as fit. Each planned
+/// transaction lists the `accounts` indexes it closes alongside its close instructions.
+pub async fn close_accounts(
+    Json(payload): Json<BulkCloseRequest>,
+) -> ApiResult<PlanTransactionsData> {
+    if payload.accounts.is_empty() {
+        return Err(Json(ApiResponse::err("accounts must not be empty")));
+    }
+    let destination = Pubkey::from_str(&payload.destination)
+        .map_err(|_| Json(ApiResponse::err("Invalid destination pubkey")))?;
+    let fee_payer = match &payload.fee_payer {
+        Some(key) => {
+            Pubkey::from_str(key).map_err(|_| Json(ApiResponse::err("Invalid fee payer pubkey")))?
+        }
+        None => destination,
+    };
+    let blockhash = match &payload.recent_blockhash {
+        Some(hash) => {
+            Hash::from_str(hash).map_err(|_| Json(ApiResponse::err("Invalid recent blockhash")))?
+        }
+        None => Hash::default(),
+    };
+
+    let mut instructions = Vec::with_capacity(payload.accounts.len());
+    for (i, target) in payload.accounts.iter().enumerate() {
+        let account = Pubkey::from_str(&target.account).map_err(|_| {
+            Json(ApiResponse::err(&format!(
+                "Invalid account pubkey at index {i}"
+            )))
+        })?;
+        let owner = Pubkey::from_str(&target.owner).map_err(|_| {
+            Json(ApiResponse::err(&format!(
+                "Invalid owner pubkey at index {i}"
+            )))
+        })?;
+        let instr = spl_token_2022::instruction::close_account(
+            &payload.token_program.id(),
+            &account,
+            &destination,
+            &owner,
+            &[],
+        )
+        .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+        instructions.push(instr);
+    }
+
+    let prefix = payload.compute_budget.instructions();
+    let constraints = BatchConstraints {
+        prefix: &prefix,
+        lookup_tables: &[],
+        units_per_instruction: None,
+        unit_limit: payload
+            .compute_budget
+            .unit_limit()
+            .unwrap_or(MAX_COMPUTE_UNIT_LIMIT),
+    };
+    let plan = plan_transactions_for(&fee_payer, &instructions, &constraints, blockhash)
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+
+    Ok(Json(ApiResponse::ok(plan)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMultisigRequest {
+    payer: String,
+    /// The new multisig account; it must sign the account creation.
+    multisig: String,
+    /// Between 1 and 11 member keys.
+    signers: Vec<String>,
+    /// How many members must sign.
+    m: u8,
+    /// Defaults to the rent-exempt minimum for a multisig account.
+    lamports: Option<u64>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn create_multisig(
+    Json(payload): Json<CreateMultisigRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let payer = Pubkey::from_str(&payload.payer)
+        .map_err(|_| Json(ApiResponse::err("Invalid payer pubkey")))?;
+    let multisig = Pubkey::from_str(&payload.multisig)
+        .map_err(|_| Json(ApiResponse::err("Invalid multisig pubkey")))?;
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    if signers.is_empty() || signers.len() > MAX_SIGNERS {
+        return Err(Json(ApiResponse::err(&format!(
+            "A multisig needs between 1 and {MAX_SIGNERS} signers"
+        ))));
+    }
+    if payload.m == 0 || payload.m as usize > signers.len() {
+        return Err(Json(ApiResponse::err(
+            "m must be at least 1 and at most the number of signers",
+        )));
+    }
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+    let lamports = payload
+        .lamports
+        .unwrap_or_else(|| Rent::default().minimum_balance(Multisig::LEN));
+
+    let initialize = spl_token_2022::instruction::initialize_multisig2(
+        &payload.token_program.id(),
+        &multisig,
+        &signer_refs,
+        payload.m,
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(system_instruction::create_account(
+        &payer,
+        &multisig,
+        lamports,
+        Multisig::LEN as u64,
+        &payload.token_program.id(),
+    ));
+    instructions.push(initialize);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WrapSolRequest {
+    owner: String,
+    lamports: u64,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Creates the owner's wSOL associated token account if needed, funds it and syncs its
+/// token balance with its lamports.
+pub async fn wrap_sol(
+    Json(payload): Json<WrapSolRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let owner = Pubkey::from_str(&payload.owner)
+        .map_err(|_| Json(ApiResponse::err("Invalid owner pubkey")))?;
+    if payload.lamports == 0 {
+        return Err(Json(ApiResponse::err("lamports must be greater than 0")));
+    }
+    let native_mint = payload.token_program.native_mint();
+    let account = get_associated_token_address_with_program_id(
+        &owner,
+        &native_mint,
+        &payload.token_program.id(),
+    );
+
+    let sync = spl_token_2022::instruction::sync_native(&payload.token_program.id(), &account)
+        .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(create_associated_token_account_idempotent(
+        &owner,
+        &owner,
+        &native_mint,
+        &payload.token_program.id(),
+    ));
+    instructions.push(system_instruction::transfer(
+        &owner,
+        &account,
+        payload.lamports,
+    ));
+    instructions.push(sync);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnwrapSolRequest {
+    owner: String,
+    /// Defaults to the owner's wSOL associated token account.
+    account: Option<String>,
+    /// Receives the unwrapped SOL; defaults to `owner`.
+    destination: Option<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Closing a wSOL account returns all of its lamports, wrapped balance included, as SOL.
+pub async fn unwrap_sol(
+    Json(payload): Json<UnwrapSolRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let owner = Pubkey::from_str(&payload.owner)
+        .map_err(|_| Json(ApiResponse::err("Invalid owner pubkey")))?;
+    let account = match &payload.account {
+        Some(key) => Pubkey::from_str(key)
+            .map_err(|_| Json(ApiResponse::err("Invalid token account pubkey")))?,
+        None => get_associated_token_address_with_program_id(
+            &owner,
+            &payload.token_program.native_mint(),
+            &payload.token_program.id(),
+        ),
+    };
+    let destination = match &payload.destination {
+        Some(key) => Pubkey::from_str(key)
+            .map_err(|_| Json(ApiResponse::err("Invalid destination pubkey")))?,
+        None => owner,
+    };
+
+    let instr = spl_token_2022::instruction::close_account(
+        &payload.token_program.id(),
+        &account,
+        &destination,
+        &owner,
+        &[],
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateTokenAccountRequest {
+    payer: String,
+    /// The new token account; it must sign the account creation.
+    account: String,
+    mint: String,
+    owner: String,
+    /// Base64 data of a Token-2022 mint, used to size the account for the mint's extensions.
+    mint_account_data: Option<String>,
+    #[serde(default)]
+    token_program: TokenProgram,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Allocates a rent-exempt token account at an arbitrary keypair address, for cases where
+/// the associated token account is not wanted.
+pub async fn create_token_account(
+    Json(payload): Json<CreateTokenAccountRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let payer = Pubkey::from_str(&payload.payer)
+        .map_err(|_| Json(ApiResponse::err("Invalid payer pubkey")))?;
+    let account = Pubkey::from_str(&payload.account)
+        .map_err(|_| Json(ApiResponse::err("Invalid token account pubkey")))?;
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+    let owner = Pubkey::from_str(&payload.owner)
+        .map_err(|_| Json(ApiResponse::err("Invalid owner pubkey")))?;
+
+    let space = match &payload.mint_account_data {
+        Some(data) => {
+            let data = BASE64
+                .decode(data)
+                .map_err(|_| Json(ApiResponse::err("Invalid base64 mint account data")))?;
+            extensions::account_len_for_mint(&data).map_err(|e| Json(ApiResponse::err(&e)))?
+        }
+        None => Account::LEN,
+    };
+    let initialize = spl_token_2022::instruction::initialize_account3(
+        &payload.token_program.id(),
+        &account,
+        &mint,
+        &owner,
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(system_instruction::create_account(
+        &payer,
+        &account,
+        Rent::default().minimum_balance(space),
+        space as u64,
+        &payload.token_program.id(),
+    ));
+    instructions.push(initialize);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
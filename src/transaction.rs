@@ -0,0 +1,377 @@
+// transaction.rs
+
+use axum::Json;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::{Message, VersionedMessage, v0},
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{Signer, keypair::keypair_from_seed},
+    transaction::VersionedTransaction,
+};
+use std::str::FromStr;
+
+use crate::limits::{TransactionLimits, check_limits};
+use crate::nonce::NonceOptions;
+use crate::{ApiResponse, ApiResult, InstructionData};
+
+#[derive(Deserialize)]
+pub struct LookupTableInput {
+    address: String,
+    addresses: Vec<String>,
+}
+
+impl LookupTableInput {
+    pub fn to_account(&self) -> Result<AddressLookupTableAccount, String> {
+        let key = Pubkey::from_str(&self.address)
+            .map_err(|_| format!("Invalid lookup table address {}", self.address))?;
+        let addresses = self
+            .addresses
+            .iter()
+            .map(|a| Pubkey::from_str(a).map_err(|_| format!("Invalid lookup table entry {a}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(AddressLookupTableAccount { key, addresses })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildTransactionRequest {
+    instructions: Vec<InstructionData>,
+    fee_payer: String,
+    recent_blockhash: Option<String>,
+    /// Builds a durable-nonce transaction instead of using `recentBlockhash`.
+    nonce: Option<NonceOptions>,
+    /// `"legacy"` or `"0"`; defaults to v0 when lookup tables are supplied, legacy otherwise.
+    version: Option<String>,
+    #[serde(default)]
+    address_lookup_tables: Vec<LookupTableInput>,
+}
+
+#[derive(Serialize)]
+struct MessageHeaderInfo {
+    num_required_signatures: u8,
+    num_readonly_signed_accounts: u8,
+    num_readonly_unsigned_accounts: u8,
+}
+
+#[derive(Serialize)]
+struct CompiledInstructionInfo {
+    program_id_index: u8,
+    accounts: Vec<u8>,
+    data: String,
+}
+
+#[derive(Serialize)]
+struct AddressTableLookupInfo {
+    account_key: String,
+    writable_indexes: Vec<u8>,
+    readonly_indexes: Vec<u8>,
+}
+
+#[derive(Serialize)]
+pub struct MessageInfo {
+    version: String,
+    header: MessageHeaderInfo,
+    account_keys: Vec<String>,
+    recent_blockhash: String,
+    instructions: Vec<CompiledInstructionInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address_table_lookups: Option<Vec<AddressTableLookupInfo>>,
+}
+
+impl From<&VersionedMessage> for MessageInfo {
+    fn from(message: &VersionedMessage) -> Self {
+        let header = message.header();
+        let (version, address_table_lookups) = match message {
+            VersionedMessage::Legacy(_) => ("legacy", None),
+            VersionedMessage::V0(m) => (
+                "0",
+                Some(
+                    m.address_table_lookups
+                        .iter()
+                        .map(|l| AddressTableLookupInfo {
+                            account_key: l.account_key.to_string(),
+                            writable_indexes: l.writable_indexes.clone(),
+                            readonly_indexes: l.readonly_indexes.clone(),
+                        })
+                        .collect(),
+                ),
+            ),
+        };
+        Self {
+            version: version.to_string(),
+            header: MessageHeaderInfo {
+                num_required_signatures: header.num_required_signatures,
+                num_readonly_signed_accounts: header.num_readonly_signed_accounts,
+                num_readonly_unsigned_accounts: header.num_readonly_unsigned_accounts,
+            },
+            account_keys: message
+                .static_account_keys()
+                .iter()
+                .map(|k| k.to_string())
+                .collect(),
+            recent_blockhash: message.recent_blockhash().to_string(),
+            instructions: message
+                .instructions()
+                .iter()
+                .map(|ix| CompiledInstructionInfo {
+                    program_id_index: ix.program_id_index,
+                    accounts: ix.accounts.clone(),
+                    data: BASE64.encode(&ix.data),
+                })
+                .collect(),
+            address_table_lookups,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct BuildTransactionData {
+    transaction: String,
+    message: MessageInfo,
+    signers: Vec<String>,
+}
+
+pub fn parse_instructions(instructions: &[InstructionData]) -> Result<Vec<Instruction>, String> {
+    if instructions.is_empty() {
+        return Err("At least one instruction is required".to_string());
+    }
+    instructions
+        .iter()
+        .enumerate()
+        .map(|(i, ix)| {
+            ix.to_instruction()
+                .map_err(|e| format!("Instruction {i}: {e}"))
+        })
+        .collect()
+}
+
+/// Compiles `instructions` into a legacy or v0 message. For v0, `CompiledKeys` moves every
+/// non-signer, non-program account found in one of the tables into a lookup entry.
+pub fn compile_message(
+    fee_payer: &Pubkey,
+    instructions: &[Instruction],
+    blockhash: Hash,
+    version: Option<&str>,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<VersionedMessage, String> {
+    let use_v0 = match version {
+        None => !lookup_tables.is_empty(),
+        Some("legacy") => false,
+        Some("0") | Some("v0") => true,
+        Some(other) => return Err(format!("Unsupported transaction version {other}")),
+    };
+    if use_v0 {
+        v0::Message::try_compile(fee_payer, instructions, lookup_tables, blockhash)
+            .map(VersionedMessage::V0)
+            .map_err(|e| format!("Message compile error: {e}"))
+    } else if !lookup_tables.is_empty() {
+        Err("Address lookup tables require a v0 transaction".to_string())
+    } else {
+        Ok(VersionedMessage::Legacy(Message::new_with_blockhash(
+            instructions,
+            Some(fee_payer),
+            &blockhash,
+        )))
+    }
+}
+
+/// Assembles the unsigned transaction described by `payload`. Without a blockhash or nonce the
+/// default hash is used when `require_blockhash` is false, which is enough for size checks.
+fn assemble_transaction(
+    payload: &BuildTransactionRequest,
+    require_blockhash: bool,
+) -> Result<VersionedTransaction, String> {
+    let fee_payer =
+        Pubkey::from_str(&payload.fee_payer).map_err(|_| "Invalid fee payer pubkey".to_string())?;
+    let mut instructions = parse_instructions(&payload.instructions)?;
+    let blockhash = match (&payload.recent_blockhash, &payload.nonce) {
+        (Some(hash), None) => {
+            Hash::from_str(hash).map_err(|_| "Invalid recent blockhash".to_string())?
+        }
+        (None, Some(nonce)) => {
+            let (hash, advance) = nonce.resolve()?;
+            instructions.insert(0, advance);
+            hash
+        }
+        (None, None) if !require_blockhash => Hash::default(),
+        _ => return Err("Provide exactly one of recentBlockhash or nonce".to_string()),
+    };
+    let lookup_tables = payload
+        .address_lookup_tables
+        .iter()
+        .map(LookupTableInput::to_account)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let message = compile_message(
+        &fee_payer,
+        &instructions,
+        blockhash,
+        payload.version.as_deref(),
+        &lookup_tables,
+    )?;
+    Ok(unsigned_transaction(message))
+}
+
+/// Wraps `message` with one empty signature slot per required signer.
+pub fn unsigned_transaction(message: VersionedMessage) -> VersionedTransaction {
+    let num_signers = message.header().num_required_signatures as usize;
+    VersionedTransaction {
+        signatures: vec![Signature::default(); num_signers],
+        message,
+    }
+}
+
+/// Rejects transactions that would not fit through the network, with the report as details.
+fn ensure_within_limits(tx: &VersionedTransaction) -> Result<(), Json<ApiResponse<()>>> {
+    let report = check_limits(tx);
+    if report.is_valid() {
+        return Ok(());
+    }
+    let message = report
+        .violations()
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join("; ");
+    Err(Json(ApiResponse::err_with_details(&message, report)))
+}
+
+pub async fn build_transaction(
+    Json(payload): Json<BuildTransactionRequest>,
+) -> ApiResult<BuildTransactionData> {
+    let tx = assemble_transaction(&payload, true).map_err(|e| Json(ApiResponse::err(&e)))?;
+    ensure_within_limits(&tx)?;
+
+    let num_signers = tx.message.header().num_required_signatures as usize;
+    let signers = tx.message.static_account_keys()[..num_signers]
+        .iter()
+        .map(|k| k.to_string())
+        .collect();
+    let wire = bincode::serialize(&tx)
+        .map_err(|e| Json(ApiResponse::err(&format!("Serialization error: {e}"))))?;
+
+    Ok(Json(ApiResponse::ok(BuildTransactionData {
+        transaction: BASE64.encode(wire),
+        message: MessageInfo::from(&tx.message),
+        signers,
+    })))
+}
+
+/// Either an already serialized transaction or the same body `/transaction/build` accepts.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ValidateTransactionRequest {
+    Serialized { transaction: String },
+    Unbuilt(BuildTransactionRequest),
+}
+
+pub async fn validate_transaction(
+    Json(payload): Json<ValidateTransactionRequest>,
+) -> ApiResult<TransactionLimits> {
+    let tx = match &payload {
+        ValidateTransactionRequest::Serialized { transaction } => {
+            deserialize_transaction(transaction)
+        }
+        ValidateTransactionRequest::Unbuilt(request) => assemble_transaction(request, false),
+    }
+    .map_err(|e| Json(ApiResponse::err(&e)))?;
+
+    Ok(Json(ApiResponse::ok(check_limits(&tx))))
+}
+
+#[derive(Deserialize)]
+pub struct SignTransactionRequest {
+    transaction: String,
+    secrets: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct SignatureSlot {
+    pubkey: String,
+    signature: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SignTransactionData {
+    transaction: String,
+    signatures: Vec<SignatureSlot>,
+    complete: bool,
+}
+
+/// Decodes a wire transaction, rejecting any whose message indexes or signature count are
+/// inconsistent so that callers may index accounts and signatures freely.
+pub fn deserialize_transaction(encoded: &str) -> Result<VersionedTransaction, String> {
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|_| "Invalid transaction encoding".to_string())?;
+    let tx: VersionedTransaction =
+        bincode::deserialize(&bytes).map_err(|_| "Invalid transaction bytes".to_string())?;
+    tx.sanitize()
+        .map_err(|e| format!("Malformed transaction: {e}"))?;
+    Ok(tx)
+}
+
+/// Fills the signature slots belonging to `secrets`, leaving every other slot untouched so
+/// that several parties can sign the same transaction in turn.
+pub async fn sign_transaction(
+    Json(payload): Json<SignTransactionRequest>,
+) -> ApiResult<SignTransactionData> {
+    let mut tx =
+        deserialize_transaction(&payload.transaction).map_err(|e| Json(ApiResponse::err(&e)))?;
+    if payload.secrets.is_empty() {
+        return Err(Json(ApiResponse::err("At least one secret is required")));
+    }
+
+    let num_signers = tx.message.header().num_required_signatures as usize;
+    let required: Vec<Pubkey> = tx.message.static_account_keys()[..num_signers].to_vec();
+
+    let message_bytes = tx.message.serialize();
+    for secret in &payload.secrets {
+        let secret_bytes = bs58::decode(secret)
+            .into_vec()
+            .map_err(|_| Json(ApiResponse::err("Invalid secret")))?;
+        if secret_bytes.len() != 64 {
+            return Err(Json(ApiResponse::err("Invalid secret bytes")));
+        }
+        // Sign with the key derived from the seed, never the stored public half: a mismatched
+        // one would produce a signature that fails verification.
+        let keypair = keypair_from_seed(&secret_bytes[..32])
+            .map_err(|_| Json(ApiResponse::err("Invalid secret bytes")))?;
+        if keypair.pubkey().as_ref() != &secret_bytes[32..] {
+            return Err(Json(ApiResponse::err(
+                "Secret key does not match its public key",
+            )));
+        }
+        let pubkey = keypair.pubkey();
+        let slot = required.iter().position(|k| *k == pubkey).ok_or_else(|| {
+            Json(ApiResponse::err(&format!(
+                "{pubkey} is not a required signer of this transaction"
+            )))
+        })?;
+        tx.signatures[slot] = keypair.sign_message(&message_bytes);
+    }
+
+    let wire = bincode::serialize(&tx)
+        .map_err(|e| Json(ApiResponse::err(&format!("Serialization error: {e}"))))?;
+    let signatures: Vec<SignatureSlot> = required
+        .iter()
+        .zip(&tx.signatures)
+        .map(|(pubkey, sig)| SignatureSlot {
+            pubkey: pubkey.to_string(),
+            signature: (*sig != Signature::default()).then(|| sig.to_string()),
+        })
+        .collect();
+    let complete = signatures.iter().all(|s| s.signature.is_some());
+
+    Ok(Json(ApiResponse::ok(SignTransactionData {
+        transaction: BASE64.encode(wire),
+        signatures,
+        complete,
+    })))
+}
@@ -0,0 +1,238 @@
+// transfer_fee.rs
+
+use axum::Json;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::{
+    extension::{
+        BaseStateWithExtensions, StateWithExtensions,
+        transfer_fee::{MAX_FEE_BASIS_POINTS, TransferFee, TransferFeeConfig, instruction},
+    },
+    state::Mint,
+};
+use std::str::FromStr;
+
+use crate::compute_budget::ComputeBudgetOptions;
+use crate::token::parse_signers;
+use crate::{ApiResponse, ApiResult, BuilderOutput, InstructionData, instruction_output};
+
+fn parse_sources(sources: &[String]) -> Result<Vec<Pubkey>, String> {
+    if sources.is_empty() {
+        return Err("sources must not be empty".to_string());
+    }
+    sources
+        .iter()
+        .map(|s| Pubkey::from_str(s).map_err(|_| format!("Invalid source pubkey: {s}")))
+        .collect()
+}
+
+/// The fee in force for a transfer, read from the mint or given directly.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeSchedule {
+    /// Base64 mint account data, as returned by `getAccountInfo`.
+    mint_account_data: Option<String>,
+    /// The current epoch; a newly set fee only takes effect from its epoch onwards.
+    epoch: Option<u64>,
+    transfer_fee_basis_points: Option<u16>,
+    maximum_fee: Option<u64>,
+}
+
+impl FeeSchedule {
+    fn resolve(&self) -> Result<TransferFee, String> {
+        let fee = match (
+            &self.mint_account_data,
+            self.transfer_fee_basis_points,
+            self.maximum_fee,
+        ) {
+            (Some(data), None, None) => {
+                let epoch = self.epoch.ok_or("epoch is required with mintAccountData")?;
+                let data = BASE64
+                    .decode(data)
+                    .map_err(|_| "Invalid base64 mint account data".to_string())?;
+                let mint = StateWithExtensions::<Mint>::unpack(&data)
+                    .map_err(|_| "Account data is not a Token-2022 mint".to_string())?;
+                let config = mint
+                    .get_extension::<TransferFeeConfig>()
+                    .map_err(|_| "Mint has no transfer fee extension".to_string())?;
+                *config.get_epoch_fee(epoch)
+            }
+            (None, Some(basis_points), Some(maximum_fee)) => TransferFee {
+                epoch: 0.into(),
+                maximum_fee: maximum_fee.into(),
+                transfer_fee_basis_points: basis_points.into(),
+            },
+            _ => {
+                return Err(
+                    "Provide either mintAccountData or transferFeeBasisPoints and maximumFee"
+                        .to_string(),
+                );
+            }
+        };
+        // Mint data is not trusted either: a fee above 100% would exceed the amount itself.
+        if u16::from(fee.transfer_fee_basis_points) > MAX_FEE_BASIS_POINTS {
+            return Err(format!(
+                "transferFeeBasisPoints must be at most {MAX_FEE_BASIS_POINTS}"
+            ));
+        }
+        Ok(fee)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalculateFeeRequest {
+    amount: u64,
+    #[serde(flatten)]
+    schedule: FeeSchedule,
+}
+
+#[derive(Serialize)]
+pub struct FeeData {
+    amount: u64,
+    /// Pass as `fee` to send_token.
+    fee: u64,
+    amount_received: u64,
+    transfer_fee_basis_points: u16,
+    maximum_fee: u64,
+}
+
+/// Computes the fee a transfer of `amount` incurs, which the recipient does not receive.
+pub async fn calculate_fee(Json(payload): Json<CalculateFeeRequest>) -> ApiResult<FeeData> {
+    let transfer_fee = payload
+        .schedule
+        .resolve()
+        .map_err(|e| Json(ApiResponse::err(&e)))?;
+    let fee = transfer_fee
+        .calculate_fee(payload.amount)
+        .ok_or_else(|| Json(ApiResponse::err("Fee calculation overflowed")))?;
+    let amount_received = payload
+        .amount
+        .checked_sub(fee)
+        .ok_or_else(|| Json(ApiResponse::err("Fee exceeds the transfer amount")))?;
+
+    Ok(Json(ApiResponse::ok(FeeData {
+        amount: payload.amount,
+        fee,
+        amount_received,
+        transfer_fee_basis_points: transfer_fee.transfer_fee_basis_points.into(),
+        maximum_fee: transfer_fee.maximum_fee.into(),
+    })))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarvestRequest {
+    mint: String,
+    /// Token accounts whose withheld fees move to the mint.
+    sources: Vec<String>,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Moves withheld fees from token accounts into the mint. Anyone may harvest, so no authority
+/// signs; accounts that cannot be harvested are skipped by the program rather than failing.
+pub async fn harvest_to_mint(
+    Json(payload): Json<HarvestRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+    let sources = parse_sources(&payload.sources).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let source_refs: Vec<&Pubkey> = sources.iter().collect();
+
+    let instr =
+        instruction::harvest_withheld_tokens_to_mint(&spl_token_2022::id(), &mint, &source_refs)
+            .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawFromMintRequest {
+    mint: String,
+    /// Token account receiving the withheld fees.
+    destination: String,
+    /// The mint's withdraw withheld authority.
+    authority: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+/// Withdraws fees previously harvested into the mint.
+pub async fn withdraw_from_mint(
+    Json(payload): Json<WithdrawFromMintRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+    let destination = Pubkey::from_str(&payload.destination)
+        .map_err(|_| Json(ApiResponse::err("Invalid destination pubkey")))?;
+    let authority = Pubkey::from_str(&payload.authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid authority pubkey")))?;
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+
+    let instr = instruction::withdraw_withheld_tokens_from_mint(
+        &spl_token_2022::id(),
+        &mint,
+        &destination,
+        &authority,
+        &signer_refs,
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawFromAccountsRequest {
+    mint: String,
+    destination: String,
+    authority: String,
+    #[serde(default)]
+    signers: Vec<String>,
+    /// Token accounts to withdraw withheld fees from directly, skipping the harvest.
+    sources: Vec<String>,
+    #[serde(flatten)]
+    compute_budget: ComputeBudgetOptions,
+}
+
+pub async fn withdraw_from_accounts(
+    Json(payload): Json<WithdrawFromAccountsRequest>,
+) -> ApiResult<BuilderOutput<InstructionData>> {
+    let mint = Pubkey::from_str(&payload.mint)
+        .map_err(|_| Json(ApiResponse::err("Invalid mint pubkey")))?;
+    let destination = Pubkey::from_str(&payload.destination)
+        .map_err(|_| Json(ApiResponse::err("Invalid destination pubkey")))?;
+    let authority = Pubkey::from_str(&payload.authority)
+        .map_err(|_| Json(ApiResponse::err("Invalid authority pubkey")))?;
+    let signers = parse_signers(&payload.signers).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let signer_refs: Vec<&Pubkey> = signers.iter().collect();
+    let sources = parse_sources(&payload.sources).map_err(|e| Json(ApiResponse::err(&e)))?;
+    let source_refs: Vec<&Pubkey> = sources.iter().collect();
+
+    let instr = instruction::withdraw_withheld_tokens_from_accounts(
+        &spl_token_2022::id(),
+        &mint,
+        &destination,
+        &authority,
+        &signer_refs,
+        &source_refs,
+    )
+    .map_err(|e| Json(ApiResponse::err(&format!("Instruction error: {e}"))))?;
+
+    let mut instructions = payload.compute_budget.instructions();
+    instructions.push(instr);
+
+    Ok(Json(ApiResponse::ok(instruction_output(instructions))))
+}